@@ -2,7 +2,16 @@ use std::env::current_dir;
 use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
-use token_vesting::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, VestingAccountResponse};
+use token_vesting::msg::{
+    AllDenomStatsResponse, AllNftInfoResponse, AllVestingAccountsResponse, ApprovalResponse,
+    ApprovalsResponse, AutoDistributionResponse, ClaimOperatorsResponse, ClaimRecipientResponse,
+    ContractInfoResponse, CreatorsResponse, Cw20HookMsg, Cw20TokensResponse, DenomStatsResponse,
+    ExecuteMsg, InstantiateMsg, MinterResponse, NftInfoResponse, NumTokensResponse,
+    OperatorResponse, OperatorsResponse, OwnerOfResponse, QueryMsg, SolvencyResponse, SudoMsg,
+    TokensResponse, ValidateVestingScheduleResponse, VestingAccountResponse,
+    VestingAccountsByMasterResponse, VestingTimelineResponse,
+};
+use token_vesting::state::Config;
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -12,6 +21,32 @@ fn main() {
 
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(Cw20HookMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(SudoMsg), &out_dir);
+    export_schema(&schema_for!(Config), &out_dir);
+    export_schema(&schema_for!(CreatorsResponse), &out_dir);
+    export_schema(&schema_for!(Cw20TokensResponse), &out_dir);
     export_schema(&schema_for!(VestingAccountResponse), &out_dir);
+    export_schema(&schema_for!(VestingAccountsByMasterResponse), &out_dir);
+    export_schema(&schema_for!(AllVestingAccountsResponse), &out_dir);
+    export_schema(&schema_for!(DenomStatsResponse), &out_dir);
+    export_schema(&schema_for!(AllDenomStatsResponse), &out_dir);
+    export_schema(&schema_for!(SolvencyResponse), &out_dir);
+    export_schema(&schema_for!(VestingTimelineResponse), &out_dir);
+    export_schema(&schema_for!(ValidateVestingScheduleResponse), &out_dir);
+    export_schema(&schema_for!(ClaimOperatorsResponse), &out_dir);
+    export_schema(&schema_for!(ClaimRecipientResponse), &out_dir);
+    export_schema(&schema_for!(AutoDistributionResponse), &out_dir);
+    export_schema(&schema_for!(OwnerOfResponse), &out_dir);
+    export_schema(&schema_for!(ApprovalResponse), &out_dir);
+    export_schema(&schema_for!(ApprovalsResponse), &out_dir);
+    export_schema(&schema_for!(OperatorResponse), &out_dir);
+    export_schema(&schema_for!(OperatorsResponse), &out_dir);
+    export_schema(&schema_for!(NftInfoResponse), &out_dir);
+    export_schema(&schema_for!(AllNftInfoResponse), &out_dir);
+    export_schema(&schema_for!(TokensResponse), &out_dir);
+    export_schema(&schema_for!(NumTokensResponse), &out_dir);
+    export_schema(&schema_for!(ContractInfoResponse), &out_dir);
+    export_schema(&schema_for!(MinterResponse), &out_dir);
 }
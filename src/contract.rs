@@ -1,16 +1,32 @@
+use crate::error::ContractError;
 use crate::msg::{
-    ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, VestingAccountResponse, VestingData,
-    VestingSchedule,
+    AllDenomStatsResponse, AllNftInfoResponse, AllVestingAccountsResponse, Approval,
+    ApprovalResponse, ApprovalsResponse, AutoDistributionResponse, ClaimOperator,
+    ClaimOperatorsResponse, ClaimRecipientResponse, ContractInfoResponse, CreatorsResponse,
+    Cw20HookMsg, Cw20TokensResponse, Cw721ReceiveMsg, DenomStatsResponse, ExecuteMsg,
+    InstantiateMsg, MigrateMsg, MinterResponse, NftInfoResponse, NumTokensResponse,
+    OperatorResponse, OperatorsResponse, OwnerOfResponse, QueryMsg, ScheduleExtension,
+    SolvencyResponse, SplitAmount, SudoMsg, TimelinePoint, TokensResponse, TransferPolicy,
+    ValidateVestingScheduleResponse, VestingAccountRegistration, VestingAccountResponse,
+    VestingAccountsByMasterResponse, VestingData, VestingSchedule, VestingTimelineResponse,
+};
+use crate::state::{
+    denom_to_key, key_to_denom, next_grant_id, vesting_accounts, Config, VestingAccount,
+    AUTO_DISTRIBUTION, CLAIM_OPERATORS, CLAIM_RECIPIENTS, CONFIG, CREATORS, CW20_TOKENS,
+    DENOM_STATS, LEGACY_APP_ID, LEGACY_VESTED_BY_DENOM, LEGACY_VESTING_ACCOUNTS, NFT_OPERATORS,
 };
-use crate::state::{denom_to_key, VestingAccount, VESTED_BY_DENOM, VESTING_ACCOUNTS};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Attribute, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Order,
-    Response, StdError, StdResult, Uint128,
+    from_json, to_json_binary, Addr, Attribute, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps,
+    DepsMut, Empty, Env, MessageInfo, Order, Response, StdError, StdResult, Storage, Uint128,
+    WasmMsg,
 };
 use cw2::set_contract_version;
-use cw20::Denom;
+use cw20::{
+    BalanceResponse as Cw20BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, Denom,
+    Expiration,
+};
 use cw_storage_plus::Bound;
 use serde_json::to_string;
 
@@ -18,32 +34,71 @@ use serde_json::to_string;
 const CONTRACT_NAME: &str = "crates.io:vesting_contract";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// cw721 collection info of the grants
+const NFT_NAME: &str = "Vesting Grants";
+const NFT_SYMBOL: &str = "GRANT";
+
+// highest share of a distribution the caller of `DistributeVested` can be paid
+const MAX_DISTRIBUTION_BOUNTY: Decimal = Decimal::percent(5);
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
-    _msg: InstantiateMsg,
-) -> StdResult<Response> {
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let owner = match msg.owner {
+        Some(owner) => deps.api.addr_validate(&owner)?,
+        None => info.sender,
+    };
+    let vesting_contract = msg
+        .vesting_contract
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let distribution_bounty = msg.distribution_bounty.unwrap_or_default();
+    assert_distribution_bounty(distribution_bounty)?;
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            owner,
+            app_id: msg.app_id,
+            vesting_contract,
+            open_registration: msg.open_registration,
+            distribution_bounty,
+        },
+    )?;
+
     Ok(Response::new())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::RegisterVestingAccount {
             master_address,
             address,
             vesting_schedule,
+            revocable,
+            transfer_policy,
         } => {
             // deposit validation
             if info.funds.len() != 1 {
-                return Err(StdError::generic_err("must deposit only one type of token"));
+                return Err(ContractError::InvalidDepositCount {
+                    count: info.funds.len(),
+                });
             } else if info.funds[0].amount.is_zero() {
-                return Err(StdError::generic_err("assert(funds > 0)"));
+                return Err(ContractError::ZeroDeposit {});
             }
 
+            assert_creator(deps.as_ref(), &info.sender)?;
             let deposit_coin = info.funds[0].clone();
 
             register_vesting_account(
@@ -51,881 +106,5852 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
                 env,
                 master_address,
                 address,
-                deposit_coin.denom.clone(),
-                deposit_coin,
+                Denom::Native(deposit_coin.denom),
+                deposit_coin.amount,
                 vesting_schedule,
+                revocable,
+                transfer_policy.unwrap_or_default(),
+            )
+        }
+        ExecuteMsg::RegisterVestingAccounts { accounts } => {
+            register_vesting_accounts(deps, env, info, accounts)
+        }
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::TopUpVestingAccount {
+            address,
+            grant_id,
+            schedule_extension,
+        } => {
+            if info.funds.len() != 1 {
+                return Err(ContractError::InvalidDepositCount {
+                    count: info.funds.len(),
+                });
+            } else if info.funds[0].amount.is_zero() {
+                return Err(ContractError::ZeroDeposit {});
+            }
+
+            let deposit_coin = info.funds[0].clone();
+            top_up_vesting_account(
+                deps,
+                info.sender,
+                address,
+                grant_id,
+                Denom::Native(deposit_coin.denom),
+                deposit_coin.amount,
+                schedule_extension,
             )
         }
-        ExecuteMsg::DeregisterVestingAccount {
+        ExecuteMsg::ApproveVestingTransfer {
+            address,
+            denom,
+            grant_id,
+            new_address,
+        } => approve_vesting_transfer(deps, info, address, denom, grant_id, new_address),
+        ExecuteMsg::UpdateConfig {
+            owner,
+            app_id,
+            vesting_contract,
+            open_registration,
+            distribution_bounty,
+        } => update_config(
+            deps,
+            info,
+            owner,
+            app_id,
+            vesting_contract,
+            open_registration,
+            distribution_bounty,
+        ),
+        ExecuteMsg::AddCreators { creators } => update_creators(deps, info, creators, true),
+        ExecuteMsg::RemoveCreators { creators } => update_creators(deps, info, creators, false),
+        ExecuteMsg::AddCw20Tokens { tokens } => update_cw20_tokens(deps, info, tokens, true),
+        ExecuteMsg::RemoveCw20Tokens { tokens } => update_cw20_tokens(deps, info, tokens, false),
+        ExecuteMsg::SweepSurplus { denom, recipient } => {
+            sweep_surplus(deps, env, info, denom, recipient)
+        }
+        ExecuteMsg::RevokeVestingAccount {
+            address,
+            denom,
+            grant_id,
+        } => revoke_vesting_account(deps, env, info, address, denom, grant_id),
+        ExecuteMsg::CancelVestingAccount {
             denom,
+            grant_id,
             vested_token_recipient,
-        } => deregister_vesting_account(deps, env, info, denom, vested_token_recipient),
-        ExecuteMsg::Claim { denoms, recipient } => claim(deps, env, info, denoms, recipient),
+        } => cancel_vesting_account(deps, env, info, denom, grant_id, vested_token_recipient),
+        ExecuteMsg::TransferVestingAccount {
+            denom,
+            grant_id,
+            new_address,
+        } => transfer_vesting_account(deps, info, denom, grant_id, new_address),
+        ExecuteMsg::SplitVestingAccount {
+            denom,
+            grant_id,
+            amount_or_ratio,
+            new_beneficiary,
+        } => split_vesting_account(
+            deps,
+            env,
+            info,
+            denom,
+            grant_id,
+            amount_or_ratio,
+            new_beneficiary,
+        ),
+        ExecuteMsg::Claim {
+            denoms,
+            grant_id,
+            recipient,
+        } => claim(deps, env, info, denoms, grant_id, recipient),
+        ExecuteMsg::ClaimAll {
+            recipient,
+            start_after,
+            limit,
+        } => claim_all(deps, env, info, recipient, start_after, limit),
+        ExecuteMsg::ApproveClaimOperator {
+            operator,
+            denom,
+            expires,
+        } => update_claim_operator(deps, env, info, operator, denom, expires, true),
+        ExecuteMsg::RevokeClaimOperator { operator, denom } => {
+            update_claim_operator(deps, env, info, operator, denom, None, false)
+        }
+        ExecuteMsg::SetClaimRecipient { recipient } => set_claim_recipient(deps, info, recipient),
+        ExecuteMsg::ClaimFor { owner, denoms } => claim_for(deps, env, info, owner, denoms),
+        ExecuteMsg::SetAutoDistribution {
+            enabled,
+            max_bounty,
+        } => set_auto_distribution(deps, info, enabled, max_bounty),
+        ExecuteMsg::DistributeVested { start_after, limit } => {
+            distribute_vested(deps, env, info, start_after, limit)
+        }
+        ExecuteMsg::TransferNft {
+            recipient,
+            token_id,
+        } => transfer_nft(deps, &env, &info, &recipient, &token_id, "transfer_nft"),
+        ExecuteMsg::SendNft {
+            contract,
+            token_id,
+            msg,
+        } => {
+            let response = transfer_nft(deps, &env, &info, &contract, &token_id, "send_nft")?;
+            let receive_msg = Cw721ReceiveMsg {
+                sender: info.sender.to_string(),
+                token_id,
+                msg,
+            };
+            Ok(response.add_message(receive_msg.into_cosmos_msg(contract)?))
+        }
+        ExecuteMsg::Approve {
+            spender,
+            token_id,
+            expires,
+        } => update_approval(deps, env, info, spender, token_id, expires, true),
+        ExecuteMsg::Revoke { spender, token_id } => {
+            update_approval(deps, env, info, spender, token_id, None, false)
+        }
+        ExecuteMsg::ApproveAll { operator, expires } => {
+            update_operator(deps, env, info, operator, expires, true)
+        }
+        ExecuteMsg::RevokeAll { operator } => {
+            update_operator(deps, env, info, operator, None, false)
+        }
+        ExecuteMsg::Burn { token_id } => burn_nft(deps, env, info, token_id),
     }
 }
 
-/// Registers a new vesting account.
-fn register_vesting_account(
+fn update_config(
     deps: DepsMut,
-    env: Env,
-    master_address: String,
-    address: String,
-    deposit_denom: String,
-    deposit: Coin,
-    vesting_schedule: VestingSchedule,
-) -> StdResult<Response> {
-    let deposit_amount = deposit.amount;
-    let deposit_denom_str = deposit.denom;
-    deps.api.addr_validate(&master_address)?;
-    deps.api.addr_validate(&address)?;
+    info: MessageInfo,
+    owner: Option<String>,
+    app_id: Option<u64>,
+    vesting_contract: Option<String>,
+    open_registration: Option<bool>,
+    distribution_bounty: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
 
-    // vesting_account existence check
-    if VESTING_ACCOUNTS.has(deps.storage, (address.as_str(), &deposit_denom)) {
-        return Err(StdError::generic_err("already exists"));
+    if let Some(owner) = owner {
+        config.owner = deps.api.addr_validate(&owner)?;
+    }
+    if let Some(app_id) = app_id {
+        config.app_id = app_id;
+    }
+    if let Some(vesting_contract) = vesting_contract {
+        config.vesting_contract = Some(deps.api.addr_validate(&vesting_contract)?);
+    }
+    if let Some(open_registration) = open_registration {
+        config.open_registration = open_registration;
     }
+    if let Some(distribution_bounty) = distribution_bounty {
+        assert_distribution_bounty(distribution_bounty)?;
+        config.distribution_bounty = distribution_bounty;
+    }
+    CONFIG.save(deps.storage, &config)?;
 
-    // validate vesting schedule
-    match vesting_schedule {
-        VestingSchedule::LinearVesting {
-            start_time,
-            end_time,
-            vesting_amount,
-        } => {
-            if vesting_amount != deposit_amount {
-                return Err(StdError::generic_err(
-                    "assert(deposit_amount == vesting_amount)",
-                ));
-            }
+    Ok(Response::new().add_attributes(vec![
+        ("action", "update_config"),
+        ("owner", config.owner.as_str()),
+        ("app_id", &config.app_id.to_string()),
+        ("open_registration", &config.open_registration.to_string()),
+        (
+            "distribution_bounty",
+            &config.distribution_bounty.to_string(),
+        ),
+    ]))
+}
 
-            if start_time < env.block.time.seconds() {
-                return Err(StdError::generic_err("assert(start_time < block_time)"));
-            }
+fn assert_distribution_bounty(bounty: Decimal) -> Result<(), ContractError> {
+    if bounty > MAX_DISTRIBUTION_BOUNTY {
+        return Err(ContractError::InvalidDistributionBounty {
+            bounty,
+            max: MAX_DISTRIBUTION_BOUNTY,
+        });
+    }
+    Ok(())
+}
 
-            if end_time <= start_time {
-                return Err(StdError::generic_err("assert(end_time <= start_time)"));
-            }
+/// Adds `creators` to or removes them from the creator allowlist.
+fn update_creators(
+    deps: DepsMut,
+    info: MessageInfo,
+    creators: Vec<String>,
+    allowed: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    for creator in creators.iter() {
+        let creator = deps.api.addr_validate(creator)?;
+        if allowed {
+            CREATORS.save(deps.storage, &creator, &Empty {})?;
+        } else {
+            CREATORS.remove(deps.storage, &creator);
         }
-        VestingSchedule::PeriodicVesting {
-            start_time,
-            end_time,
-            vesting_interval,
-            amount,
-        } => {
-            if amount.is_zero() {
-                return Err(StdError::generic_err(
-                    "cannot make zero token vesting account",
-                ));
-            }
+    }
 
-            if start_time < env.block.time.seconds() {
-                return Err(StdError::generic_err("invalid start_time"));
-            }
+    let action = if allowed {
+        "add_creators"
+    } else {
+        "remove_creators"
+    };
+    Ok(Response::new().add_attributes(vec![("action", action), ("creators", &creators.join(","))]))
+}
 
-            if end_time <= start_time {
-                return Err(StdError::generic_err("assert(end_time > start_time)"));
-            }
+fn update_cw20_tokens(
+    deps: DepsMut,
+    info: MessageInfo,
+    tokens: Vec<String>,
+    accepted: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
 
-            if vesting_interval == 0 {
-                return Err(StdError::generic_err("assert(vesting_interval != 0)"));
-            }
+    for token in tokens.iter() {
+        let token = deps.api.addr_validate(token)?;
+        if accepted {
+            CW20_TOKENS.save(deps.storage, &token, &Empty {})?;
+        } else {
+            CW20_TOKENS.remove(deps.storage, &token);
+        }
+    }
 
-            let time_period = end_time - start_time;
-            if time_period != (time_period / vesting_interval) * vesting_interval {
-                return Err(StdError::generic_err(
-                    "assert((end_time - start_time) % vesting_interval == 0)",
-                ));
-            }
+    let action = if accepted {
+        "add_cw20_tokens"
+    } else {
+        "remove_cw20_tokens"
+    };
+    Ok(Response::new().add_attributes(vec![("action", action), ("tokens", &tokens.join(","))]))
+}
 
-            let num_interval = time_period / vesting_interval;
-            let vesting_amount = amount.checked_mul(Uint128::from(num_interval))?;
-            if vesting_amount != deposit_amount {
-                return Err(StdError::generic_err(
-                    "assert(deposit_amount = amount * ((end_time - start_time) / vesting_interval))",
-                ));
-            }
+/// Sends the part of the contract balance of `denom` which is not owed to any grant.
+fn sweep_surplus(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: Denom,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
+
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let recipient = recipient.unwrap_or_else(|| info.sender.to_string());
+    deps.api.addr_validate(&recipient)?;
+
+    let solvency = solvency(deps.as_ref(), &env, denom)?;
+    if solvency.surplus.is_zero() {
+        return Err(ContractError::NoSurplus {
+            denom: denom_to_key(&solvency.denom),
+        });
+    }
+
+    Ok(Response::new()
+        .add_message(transfer_msg(
+            &solvency.denom,
+            recipient.clone(),
+            solvency.surplus,
+        )?)
+        .add_attributes(vec![
+            ("action", "sweep_surplus"),
+            ("vesting_denom", &to_string(&solvency.denom).unwrap()),
+            ("recipient", &recipient),
+            ("amount", &solvency.surplus.to_string()),
+        ]))
+}
+
+/// Fails unless `creator` may register vesting accounts.
+fn assert_creator(deps: Deps, creator: &Addr) -> Result<(), ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !config.open_registration && !CREATORS.has(deps.storage, creator) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// Handles cw20 deposits. The sending token contract is the vesting denom.
+fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
+
+    // anyone can call the hook, so `cw20_msg.sender` is only trusted for accepted tokens
+    if !CW20_TOKENS.has(deps.storage, &info.sender) {
+        return Err(ContractError::Cw20TokenNotAccepted {
+            token: info.sender.to_string(),
+        });
+    }
+
+    let deposit_amount = cw20_msg.amount;
+    if deposit_amount.is_zero() {
+        return Err(ContractError::ZeroDeposit {});
+    }
+
+    let sender = deps.api.addr_validate(&cw20_msg.sender)?;
+    match from_json(&cw20_msg.msg)? {
+        Cw20HookMsg::RegisterVestingAccount {
+            master_address,
+            address,
+            vesting_schedule,
+            revocable,
+            transfer_policy,
+        } => {
+            assert_creator(deps.as_ref(), &sender)?;
+            register_vesting_account(
+                deps,
+                env,
+                master_address,
+                address,
+                Denom::Cw20(info.sender),
+                deposit_amount,
+                vesting_schedule,
+                revocable,
+                transfer_policy.unwrap_or_default(),
+            )
         }
+        Cw20HookMsg::TopUpVestingAccount {
+            address,
+            grant_id,
+            schedule_extension,
+        } => top_up_vesting_account(
+            deps,
+            sender,
+            address,
+            grant_id,
+            Denom::Cw20(info.sender),
+            deposit_amount,
+            schedule_extension,
+        ),
     }
+}
 
-    VESTING_ACCOUNTS.save(
+/// Registers a new vesting account under a new grant id.
+#[allow(clippy::too_many_arguments)]
+fn register_vesting_account(
+    deps: DepsMut,
+    env: Env,
+    master_address: String,
+    address: String,
+    deposit_denom: Denom,
+    deposit_amount: Uint128,
+    vesting_schedule: VestingSchedule,
+    revocable: bool,
+    transfer_policy: TransferPolicy,
+) -> Result<Response, ContractError> {
+    let denom_key = denom_to_key(&deposit_denom);
+    let block_time = env.block.time.seconds();
+    deps.api.addr_validate(&master_address)?;
+    deps.api.addr_validate(&address)?;
+
+    vesting_schedule.validate(block_time, deposit_amount)?;
+
+    let grant_id = next_grant_id(deps.storage)?;
+    vesting_accounts().save(
         deps.storage,
-        (address.as_str(), &deposit_denom_str),
+        (address.as_str(), &denom_key, grant_id),
         &VestingAccount {
+            grant_id,
             master_address: master_address.clone(),
             address: address.to_string(),
             vesting_denom: deposit_denom.clone(),
             vesting_amount: deposit_amount,
             vesting_schedule,
             claimed_amount: Uint128::zero(),
+            revocable,
+            transfer_policy,
+            pending_transfer: None,
+            approvals: vec![],
         },
     )?;
 
-    let total_vested = match VESTED_BY_DENOM.may_load(deps.storage, &deposit_denom_str)? {
-        Some(data) => data,
-        None => Uint128::new(0),
-    };
-    VESTED_BY_DENOM.save(
-        deps.storage,
-        &deposit_denom_str,
-        &(deposit_amount + total_vested),
-    )?;
+    DENOM_STATS.update(deps.storage, &denom_key, |stats| -> StdResult<_> {
+        let mut stats = stats.unwrap_or_default();
+        stats.deposited = stats.deposited.checked_add(deposit_amount)?;
+        stats.outstanding = stats.outstanding.checked_add(deposit_amount)?;
+        stats.active_grants += 1;
+        Ok(stats)
+    })?;
 
     Ok(Response::new().add_attributes(vec![
         ("action", "register_vesting_account"),
         ("master_address", master_address.as_str()),
         ("address", address.as_str()),
+        ("grant_id", &grant_id.to_string()),
         ("vesting_denom", &to_string(&deposit_denom).unwrap()),
         ("vesting_amount", &deposit_amount.to_string()),
+        ("revocable", &revocable.to_string()),
     ]))
 }
 
-fn deregister_vesting_account(
+/// Adds a deposit to an existing grant, keeping its grant id and claim history.
+fn top_up_vesting_account(
     deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    denom: String,
-    vested_token_recipient: Option<String>,
-) -> StdResult<Response> {
-    if !info.funds.is_empty() {
-        return Err(StdError::GenericErr {
-            msg: String::from("Funds not allowed."),
-        });
-    };
-    let sender = info.sender;
-
-    let mut messages: Vec<CosmosMsg> = vec![];
-
-    // vesting_account existence check
-    let account = VESTING_ACCOUNTS.may_load(deps.storage, (sender.as_str(), &denom))?;
-    if account.is_none() {
-        return Err(StdError::generic_err(format!(
-            "vesting entry is not found for denom {:?}",
-            to_string(&denom).unwrap(),
-        )));
+    sender: Addr,
+    address: String,
+    grant_id: u64,
+    deposit_denom: Denom,
+    deposit_amount: Uint128,
+    schedule_extension: ScheduleExtension,
+) -> Result<Response, ContractError> {
+    let denom_key = denom_to_key(&deposit_denom);
+    let key = (address.as_str(), denom_key.as_str(), grant_id);
+    let mut account = vesting_accounts()
+        .may_load(deps.storage, key)?
+        .ok_or_else(|| ContractError::VestingAccountNotFound {
+            denom: denom_key.clone(),
+            grant_id: Some(grant_id),
+        })?;
+    if account.master_address != sender {
+        return Err(ContractError::Unauthorized {});
     }
 
-    let account = account.unwrap();
-    let master_account = account.master_address;
-    // remove vesting account
-    VESTING_ACCOUNTS.remove(deps.storage, (sender.as_str(), &denom));
-
-    let vested_amount = account
+    account.vesting_schedule = account
         .vesting_schedule
-        .vested_amount(env.block.time.seconds())?;
-    let claimed_amount = account.claimed_amount;
+        .extend(&schedule_extension, deposit_amount)?;
+    account.vesting_amount = account.vesting_amount.checked_add(deposit_amount)?;
+    vesting_accounts().save(deps.storage, key, &account)?;
 
-    // transfer already vested but not claimed amount to
-    // a account address or the given `vested_token_recipient` address
-    let claimable_amount = vested_amount.checked_sub(claimed_amount)?;
-    if !claimable_amount.is_zero() {
-        let recipient = vested_token_recipient.unwrap_or_else(|| sender.to_string());
-        deps.api.addr_validate(&recipient)?;
+    DENOM_STATS.update(deps.storage, &denom_key, |stats| -> StdResult<_> {
+        let mut stats = stats.unwrap_or_default();
+        stats.deposited = stats.deposited.checked_add(deposit_amount)?;
+        stats.outstanding = stats.outstanding.checked_add(deposit_amount)?;
+        Ok(stats)
+    })?;
 
-        let message: CosmosMsg = BankMsg::Send {
-            to_address: recipient,
-            amount: vec![Coin {
-                denom: account.vesting_denom.clone(),
-                amount: claimable_amount,
-            }],
+    Ok(Response::new().add_attributes(vec![
+        ("action", "top_up_vesting_account"),
+        ("master_address", sender.as_str()),
+        ("address", address.as_str()),
+        ("grant_id", &grant_id.to_string()),
+        ("vesting_denom", &to_string(&deposit_denom).unwrap()),
+        ("top_up_amount", &deposit_amount.to_string()),
+        ("vesting_amount", &account.vesting_amount.to_string()),
+    ]))
+}
+
+/// Registers a batch of natively funded grants. All grants are created or none.
+fn register_vesting_accounts(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    accounts: Vec<VestingAccountRegistration>,
+) -> Result<Response, ContractError> {
+    if accounts.is_empty() {
+        return Err(ContractError::EmptyBatch {});
+    }
+    assert_creator(deps.as_ref(), &info.sender)?;
+
+    // every sent coin must be fully assigned to grants, and every grant funded
+    let mut totals: Vec<Coin> = info
+        .funds
+        .iter()
+        .map(|coin| Coin {
+            denom: coin.denom.clone(),
+            amount: Uint128::zero(),
+        })
+        .collect();
+    let mut vesting_amounts: Vec<Uint128> = vec![];
+    for account in accounts.iter() {
+        let vesting_amount = account.vesting_schedule.total_amount()?;
+        match totals.iter_mut().find(|coin| coin.denom == account.denom) {
+            Some(coin) => coin.amount = coin.amount.checked_add(vesting_amount)?,
+            None => totals.push(Coin {
+                denom: account.denom.clone(),
+                amount: vesting_amount,
+            }),
+        }
+        vesting_amounts.push(vesting_amount);
+    }
+    for total in totals {
+        let deposit_amount = info
+            .funds
+            .iter()
+            .find(|coin| coin.denom == total.denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default();
+        if deposit_amount != total.amount {
+            return Err(ContractError::BatchDepositMismatch {
+                denom: total.denom,
+                deposit_amount,
+                vesting_amount: total.amount,
+            });
         }
-        .into();
+    }
 
-        messages.push(message);
+    let count = accounts.len();
+    let mut attrs: Vec<Attribute> = vec![];
+    for (account, vesting_amount) in accounts.into_iter().zip(vesting_amounts) {
+        let response = register_vesting_account(
+            deps.branch(),
+            env.clone(),
+            account.master_address,
+            account.address,
+            Denom::Native(account.denom),
+            vesting_amount,
+            account.vesting_schedule,
+            account.revocable,
+            account.transfer_policy.unwrap_or_default(),
+        )?;
+        attrs.extend(response.attributes);
     }
 
-    // transfer left vesting amount to owner or
-    // the given `left_vesting_token_recipient` address
-    let left_vesting_amount = account.vesting_amount.checked_sub(vested_amount)?;
-    if !left_vesting_amount.is_zero() {
-        let recipient = master_account;
-        deps.api.addr_validate(&recipient)?;
-        let message: CosmosMsg = BankMsg::Send {
-            to_address: recipient,
-            amount: vec![Coin {
-                denom: account.vesting_denom.clone(),
-                amount: left_vesting_amount,
-            }],
-        }
-        .into();
+    Ok(Response::new()
+        .add_attributes(vec![
+            ("action", "register_vesting_accounts"),
+            ("count", &count.to_string()),
+        ])
+        .add_attributes(attrs))
+}
 
-        messages.push(message);
+/// Loads the grants of `address` for `denom`, or only the given grant when
+/// `grant_id` is set. Fails if no grant matches.
+fn load_grants(
+    deps: Deps,
+    address: &str,
+    denom: &Denom,
+    grant_id: Option<u64>,
+) -> Result<Vec<VestingAccount>, ContractError> {
+    let accounts = may_load_grants(deps, address, denom, grant_id)?;
+    if accounts.is_empty() {
+        return Err(ContractError::VestingAccountNotFound {
+            denom: denom_to_key(denom),
+            grant_id,
+        });
     }
 
-    let total_vested = match VESTED_BY_DENOM.may_load(deps.storage, &denom)? {
-        Some(data) => data,
-        None => Uint128::new(0),
-    };
-    VESTED_BY_DENOM.save(
-        deps.storage,
-        &denom,
-        &(total_vested - left_vesting_amount - claimable_amount),
-    )?;
+    Ok(accounts)
+}
 
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
-        ("action", "deregister_vesting_account"),
-        ("address", sender.as_str()),
-        ("vesting_denom", &to_string(&account.vesting_denom).unwrap()),
-        ("vesting_amount", &account.vesting_amount.to_string()),
-        ("vested_amount", &vested_amount.to_string()),
-        ("left_vesting_amount", &left_vesting_amount.to_string()),
-    ]))
+/// Like `load_grants`, but returns no grants instead of failing.
+fn may_load_grants(
+    deps: Deps,
+    address: &str,
+    denom: &Denom,
+    grant_id: Option<u64>,
+) -> StdResult<Vec<VestingAccount>> {
+    let denom_key = denom_to_key(denom);
+    Ok(match grant_id {
+        Some(grant_id) => vesting_accounts()
+            .may_load(deps.storage, (address, &denom_key, grant_id))?
+            .into_iter()
+            .collect(),
+        None => vesting_accounts()
+            .prefix((address, &denom_key))
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, account)| account))
+            .collect::<StdResult<Vec<_>>>()?,
+    })
 }
 
-fn claim(
+/// Builds the message transferring `amount` of `denom` to `recipient`.
+fn transfer_msg(denom: &Denom, recipient: String, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(match denom {
+        Denom::Native(denom) => BankMsg::Send {
+            to_address: recipient,
+            amount: vec![Coin {
+                denom: denom.clone(),
+                amount,
+            }],
+        }
+        .into(),
+        Denom::Cw20(contract_addr) => WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer { recipient, amount })?,
+            funds: vec![],
+        }
+        .into(),
+    })
+}
+
+/// Cancels grants of the sender. Called by the beneficiary.
+fn cancel_vesting_account(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    denoms: Vec<String>,
-    recipient: Option<String>,
-) -> StdResult<Response> {
+    denom: Denom,
+    grant_id: Option<u64>,
+    vested_token_recipient: Option<String>,
+) -> Result<Response, ContractError> {
     if !info.funds.is_empty() {
-        return Err(StdError::GenericErr {
-            msg: "Funds not allowed.".to_string(),
-        });
+        return Err(ContractError::FundsNotAllowed {});
     };
-
     let sender = info.sender;
-    let recipient = recipient.unwrap_or_else(|| sender.to_string());
+
+    // vesting_account existence check
+    let accounts = load_grants(deps.as_ref(), sender.as_str(), &denom, grant_id)?;
+
+    let recipient = vested_token_recipient.unwrap_or_else(|| sender.to_string());
     deps.api.addr_validate(&recipient)?;
 
-    let mut messages: Vec<CosmosMsg> = vec![];
-    let mut attrs: Vec<Attribute> = vec![];
-    for denom in denoms.iter() {
-        // vesting_account existence check
-        let account = VESTING_ACCOUNTS.may_load(deps.storage, (sender.as_str(), denom))?;
-        if account.is_none() {
-            return Err(StdError::generic_err(format!(
-                "vesting entry is not found for denom {}",
-                to_string(&denom).unwrap(),
-            )));
-        }
+    deregister_vesting_account(
+        deps,
+        env,
+        sender.as_str(),
+        &denom,
+        accounts,
+        recipient,
+        "cancel_vesting_account",
+    )
+}
 
-        let mut account = account.unwrap();
-        let vested_amount = account
-            .vesting_schedule
-            .vested_amount(env.block.time.seconds())?;
-        let claimed_amount = account.claimed_amount;
+/// Moves grants of the sender to `new_address`, or marks them for transfer
+/// if the master has to approve. Called by the beneficiary.
+fn transfer_vesting_account(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: Denom,
+    grant_id: Option<u64>,
+    new_address: String,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
+    let sender = info.sender;
+    deps.api.addr_validate(&new_address)?;
+    if new_address == sender {
+        return Err(ContractError::InvalidTransferRecipient {});
+    }
 
-        let claimable_amount = vested_amount.checked_sub(claimed_amount)?;
-        if claimable_amount.is_zero() {
-            continue;
-        }
+    // vesting_account existence check
+    let accounts = load_grants(deps.as_ref(), sender.as_str(), &denom, grant_id)?;
+
+    // non-transferable grants stay with the sender
+    let accounts: Vec<VestingAccount> = accounts
+        .into_iter()
+        .filter(|account| account.transfer_policy != TransferPolicy::NonTransferable)
+        .collect();
+    if accounts.is_empty() {
+        return Err(ContractError::NotTransferable {
+            denom: denom_to_key(&denom),
+            grant_id,
+        });
+    }
 
-        account.claimed_amount = vested_amount;
-        if account.claimed_amount == account.vesting_amount {
-            VESTING_ACCOUNTS.remove(deps.storage, (sender.as_str(), denom));
+    let denom_key = denom_to_key(&denom);
+    let mut attrs: Vec<Attribute> = vec![];
+    for mut account in accounts {
+        if account.transfer_policy == TransferPolicy::MasterApproval {
+            account.pending_transfer = Some(new_address.clone());
+            vesting_accounts().save(
+                deps.storage,
+                (sender.as_str(), &denom_key, account.grant_id),
+                &account,
+            )?;
+            attrs.push(Attribute::new(
+                "pending_grant_id",
+                account.grant_id.to_string(),
+            ));
         } else {
-            VESTING_ACCOUNTS.save(deps.storage, (sender.as_str(), denom), &account)?;
+            attrs.push(Attribute::new(
+                "transferred_grant_id",
+                account.grant_id.to_string(),
+            ));
+            move_grant(deps.storage, account, new_address.clone())?;
         }
+    }
 
-        let message: CosmosMsg = BankMsg::Send {
-            to_address: recipient.clone(),
-            amount: vec![Coin {
-                denom: account.vesting_denom.clone(),
-                amount: claimable_amount,
-            }],
-        }
-        .into();
-        messages.push(message);
-        attrs.extend(
-            vec![
-                Attribute::new("vesting_denom", &to_string(&account.vesting_denom).unwrap()),
-                Attribute::new("vesting_amount", &account.vesting_amount.to_string()),
-                Attribute::new("vested_amount", &vested_amount.to_string()),
-                Attribute::new("claim_amount", &claimable_amount.to_string()),
-            ]
-            .into_iter(),
-        );
-
-        let total_vested = VESTED_BY_DENOM.may_load(deps.storage, denom)?;
+    Ok(Response::new()
+        .add_attributes(vec![
+            ("action", "transfer_vesting_account"),
+            ("address", sender.as_str()),
+            ("new_address", new_address.as_str()),
+            ("vesting_denom", &to_string(&denom).unwrap()),
+        ])
+        .add_attributes(attrs))
+}
 
-        if total_vested.is_none() {
-            return Err(StdError::generic_err("already exists"));
-        };
+/// Carries out the pending transfer of a grant of `address`. Called by the master.
+fn approve_vesting_transfer(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    denom: Denom,
+    grant_id: u64,
+    new_address: String,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
 
-        VESTED_BY_DENOM.save(
-            deps.storage,
-            denom,
-            &(total_vested.unwrap() - claimable_amount),
-        )?;
+    let account = load_grants(deps.as_ref(), &address, &denom, Some(grant_id))?.remove(0);
+    if account.master_address != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    // the beneficiary may replace the request while the approval is in flight
+    let pending_transfer = account
+        .pending_transfer
+        .clone()
+        .ok_or(ContractError::NoPendingTransfer { grant_id })?;
+    if pending_transfer != new_address {
+        return Err(ContractError::PendingTransferMismatch {
+            grant_id,
+            pending_transfer,
+        });
     }
+    move_grant(deps.storage, account, new_address.clone())?;
 
-    Ok(Response::new()
-        .add_messages(messages)
-        .add_attributes(vec![("action", "claim"), ("address", sender.as_str())])
-        .add_attributes(attrs))
+    Ok(Response::new().add_attributes(vec![
+        ("action", "approve_vesting_transfer"),
+        ("master_address", info.sender.as_str()),
+        ("address", address.as_str()),
+        ("new_address", new_address.as_str()),
+        ("grant_id", &grant_id.to_string()),
+        ("vesting_denom", &to_string(&denom).unwrap()),
+    ]))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::VestingAccount {
-            address,
-            start_after,
-            limit,
-        } => to_binary(&vesting_account(deps, env, address, start_after, limit)?),
-        QueryMsg::VestedTokens { denom } => to_binary(&vested_tokens(deps, env, denom)?),
-    }
+/// Re-keys a grant to `new_address`, keeping its grant id and claimed amount.
+fn move_grant(
+    storage: &mut dyn Storage,
+    mut account: VestingAccount,
+    new_address: String,
+) -> StdResult<()> {
+    let denom_key = denom_to_key(&account.vesting_denom);
+    vesting_accounts().remove(
+        storage,
+        (account.address.as_str(), &denom_key, account.grant_id),
+    )?;
+    account.address = new_address;
+    account.pending_transfer = None;
+    account.approvals = vec![];
+    vesting_accounts().save(
+        storage,
+        (account.address.as_str(), &denom_key, account.grant_id),
+        &account,
+    )
 }
 
-const MAX_LIMIT: u32 = 30;
-const DEFAULT_LIMIT: u32 = 10;
-fn vesting_account(
-    deps: Deps,
+/// Splits part of a grant off into a new grant of `new_beneficiary` with the same terms.
+/// Called by the beneficiary or the master.
+fn split_vesting_account(
+    mut deps: DepsMut,
     env: Env,
-    address: String,
-    start_after: Option<Denom>,
-    limit: Option<u32>,
-) -> StdResult<VestingAccountResponse> {
-    let mut vestings: Vec<VestingData> = vec![];
-    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    deps.api.addr_validate(&address)?;
+    info: MessageInfo,
+    denom: Denom,
+    grant_id: u64,
+    amount_or_ratio: SplitAmount,
+    new_beneficiary: String,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
+    deps.api.addr_validate(&new_beneficiary)?;
+
+    let denom_key = denom_to_key(&denom);
+    let mut account = vesting_accounts()
+        .idx
+        .grant
+        .item(deps.storage, grant_id)?
+        .map(|(_, account)| account)
+        .filter(|account| account.vesting_denom == denom)
+        .ok_or_else(|| ContractError::VestingAccountNotFound {
+            denom: denom_key.clone(),
+            grant_id: Some(grant_id),
+        })?;
+
+    // beneficiaries may only give away what they could transfer,
+    // masters only what they could revoke
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut settle_attrs: Vec<Attribute> = vec![];
+    if account.address == info.sender {
+        if account.transfer_policy != TransferPolicy::Transferable {
+            return Err(ContractError::NotTransferable {
+                denom: denom_key,
+                grant_id: Some(grant_id),
+            });
+        }
+    } else if account.master_address == info.sender {
+        if !account.revocable {
+            return Err(ContractError::NotRevocable {
+                denom: denom_key,
+                grant_id: Some(grant_id),
+            });
+        }
 
-    for item in VESTING_ACCOUNTS
-        .prefix(address.as_str())
-        .range(
-            deps.storage,
-            start_after
-                .map(denom_to_key)
-                .map(|v| v.as_bytes().to_vec())
-                .map(Bound::ExclusiveRaw),
-            None,
-            Order::Ascending,
-        )
-        .take(limit)
-    {
-        let (_, account) = item?;
+        // vested tokens belong to the beneficiary, so they are paid out
+        // and the master only divides what is still unvested
         let vested_amount = account
             .vesting_schedule
             .vested_amount(env.block.time.seconds())?;
+        if vested_amount >= account.vesting_amount {
+            return Err(ContractError::FullyVested { grant_id });
+        }
+        let address = account.address.clone();
+        let (vested_amount, claimable_amount) =
+            claim_vested(deps.branch(), &env, &address, &account)?;
+        if !claimable_amount.is_zero() {
+            let recipient = claim_recipient(deps.as_ref(), address)?.recipient;
+            messages.push(transfer_msg(
+                &account.vesting_denom,
+                recipient,
+                claimable_amount,
+            )?);
+            settle_attrs = claim_attributes(&account, vested_amount, claimable_amount);
+            account.claimed_amount = vested_amount;
+        }
+    } else {
+        return Err(ContractError::Unauthorized {});
+    }
 
-        vestings.push(VestingData {
-            master_address: account.master_address,
-            vesting_denom: account.vesting_denom,
-            vesting_amount: account.vesting_amount,
-            vested_amount,
-            vesting_schedule: account.vesting_schedule,
-            claimable_amount: vested_amount.checked_sub(account.claimed_amount)?,
-        })
+    let (remaining_schedule, split_schedule) = account.vesting_schedule.split(&amount_or_ratio)?;
+    let split_amount = split_schedule.total_amount()?;
+    // rounding down favours the new grant; the original keeps at most its new total claimed
+    let split_claimed = account
+        .claimed_amount
+        .multiply_ratio(split_amount, account.vesting_amount);
+
+    let new_grant_id = next_grant_id(deps.storage)?;
+    let new_account = VestingAccount {
+        grant_id: new_grant_id,
+        address: new_beneficiary.clone(),
+        vesting_amount: split_amount,
+        vesting_schedule: split_schedule,
+        claimed_amount: split_claimed,
+        pending_transfer: None,
+        approvals: vec![],
+        ..account.clone()
+    };
+    vesting_accounts().save(
+        deps.storage,
+        (new_beneficiary.as_str(), &denom_key, new_grant_id),
+        &new_account,
+    )?;
+
+    account.vesting_amount = account.vesting_amount.checked_sub(split_amount)?;
+    account.vesting_schedule = remaining_schedule;
+    account.claimed_amount = account.claimed_amount.checked_sub(split_claimed)?;
+    let key = (account.address.as_str(), denom_key.as_str(), grant_id);
+    let mut stats = DENOM_STATS.load(deps.storage, &denom_key)?;
+    if account.claimed_amount == account.vesting_amount {
+        vesting_accounts().remove(deps.storage, key)?;
+    } else {
+        vesting_accounts().save(deps.storage, key, &account)?;
+        stats.active_grants += 1;
     }
+    DENOM_STATS.save(deps.storage, &denom_key, &stats)?;
 
-    Ok(VestingAccountResponse { address, vestings })
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            ("action", "split_vesting_account"),
+            ("address", account.address.as_str()),
+            ("new_beneficiary", new_beneficiary.as_str()),
+            ("grant_id", &grant_id.to_string()),
+            ("new_grant_id", &new_grant_id.to_string()),
+            ("vesting_denom", &to_string(&denom).unwrap()),
+            ("split_amount", &split_amount.to_string()),
+        ])
+        .add_attributes(settle_attrs))
 }
 
-fn vested_tokens(deps: Deps, _env: Env, denom: String) -> StdResult<Uint128> {
-    let total_vested = match VESTED_BY_DENOM.may_load(deps.storage, &denom)? {
-        Some(data) => data,
-        None => Uint128::new(0),
-    };
-    Ok(total_vested)
+/// Loads the grant behind a cw721 token id, which is the grant id.
+fn load_token(deps: Deps, token_id: &str) -> Result<VestingAccount, ContractError> {
+    let grant_id: u64 = token_id
+        .parse()
+        .map_err(|_| ContractError::InvalidTokenId {
+            token_id: token_id.to_string(),
+        })?;
+    vesting_accounts()
+        .idx
+        .grant
+        .item(deps.storage, grant_id)?
+        .map(|(_, account)| account)
+        .ok_or_else(|| ContractError::TokenNotFound {
+            token_id: token_id.to_string(),
+        })
 }
 
-#[entry_point]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, StdError> {
-    let ver = cw2::get_contract_version(deps.storage)?;
-    // ensure we are migrating from an allowed contract
-    if ver.contract != CONTRACT_NAME {
-        return Err(StdError::generic_err("Can only upgrade from same type"));
+/// `load_token` for queries, which fail with a `StdError`.
+fn query_token(deps: Deps, token_id: &str) -> StdResult<VestingAccount> {
+    load_token(deps, token_id).map_err(|err| match err {
+        ContractError::Std(err) => err,
+        ContractError::TokenNotFound { token_id } => {
+            StdError::not_found(format!("grant {}", token_id))
+        }
+        err => StdError::generic_err(err.to_string()),
+    })
+}
+
+/// Whether `operator` holds an unexpired operator approval of `owner`.
+fn is_operator(deps: Deps, env: &Env, owner: &str, operator: &str) -> StdResult<bool> {
+    let expires = NFT_OPERATORS.may_load(deps.storage, (owner, operator))?;
+    Ok(expires.is_some_and(|expires| !expires.is_expired(&env.block)))
+}
+
+/// Checks that `sender` may move or burn `account`: the holder, an unexpired spender
+/// of the grant or an operator of the holder.
+fn assert_can_send(
+    deps: Deps,
+    env: &Env,
+    sender: &Addr,
+    account: &VestingAccount,
+) -> Result<(), ContractError> {
+    if account.address == *sender {
+        return Ok(());
     }
-    // note: better to do proper semver compare, but string compare *usually* works
-    if ver.version.as_str() > CONTRACT_VERSION {
-        return Err(StdError::generic_err("Cannot upgrade from a newer version"));
+    let approved = account
+        .approvals
+        .iter()
+        .any(|approval| approval.spender == *sender && !approval.expires.is_expired(&env.block));
+    if approved || is_operator(deps, env, &account.address, sender.as_str())? {
+        return Ok(());
     }
+    Err(ContractError::Unauthorized {})
+}
 
-    // set the new version
-    cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-
-    // do any desired state migrations...
+/// Moves the grant behind `token_id` to `recipient`.
+/// Called by the holder, an approved spender or an operator of the holder.
+fn transfer_nft(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    recipient: &str,
+    token_id: &str,
+    action: &str,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
+    deps.api.addr_validate(recipient)?;
+
+    let account = load_token(deps.as_ref(), token_id)?;
+    assert_can_send(deps.as_ref(), env, &info.sender, &account)?;
+    // the master keeps the say over grants requiring approval
+    if account.transfer_policy != TransferPolicy::Transferable {
+        return Err(ContractError::NotTransferable {
+            denom: denom_to_key(&account.vesting_denom),
+            grant_id: Some(account.grant_id),
+        });
+    }
+    if account.address == recipient {
+        return Err(ContractError::InvalidTransferRecipient {});
+    }
+    move_grant(deps.storage, account, recipient.to_string())?;
 
-    Ok(Response::default())
+    Ok(Response::new().add_attributes(vec![
+        ("action", action),
+        ("sender", info.sender.as_str()),
+        ("recipient", recipient),
+        ("token_id", token_id),
+    ]))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coin, coins, Addr, BankMsg, CosmosMsg, StdError, Timestamp};
+/// Adds or removes a spender of the grant behind `token_id`.
+/// Called by the holder or an operator of the holder.
+fn update_approval(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    token_id: String,
+    expires: Option<Expiration>,
+    approve: bool,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
+    deps.api.addr_validate(&spender)?;
 
-    const DENOM: &str = "TKN";
+    let mut account = load_token(deps.as_ref(), &token_id)?;
+    if account.address != info.sender
+        && !is_operator(deps.as_ref(), &env, &account.address, info.sender.as_str())?
+    {
+        return Err(ContractError::Unauthorized {});
+    }
 
-    #[test]
-    fn proper_initialization() {
-        let env = mock_env();
-        let mut deps = mock_dependencies();
-        let info = mock_info("sender", &coins(0, DENOM.to_string()));
+    account
+        .approvals
+        .retain(|approval| approval.spender != spender);
+    if approve {
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+        account.approvals.push(Approval {
+            spender: spender.clone(),
+            expires,
+        });
+    }
+    let denom_key = denom_to_key(&account.vesting_denom);
+    vesting_accounts().save(
+        deps.storage,
+        (account.address.as_str(), &denom_key, account.grant_id),
+        &account,
+    )?;
 
-        let msg = InstantiateMsg {};
-        let res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
-        assert_eq!(res.messages.len(), 0);
-        assert_eq!(res.attributes.len(), 0);
+    Ok(Response::new().add_attributes(vec![
+        ("action", if approve { "approve" } else { "revoke" }),
+        ("sender", info.sender.as_str()),
+        ("spender", spender.as_str()),
+        ("token_id", token_id.as_str()),
+    ]))
+}
+
+/// Adds or removes an operator of all grants of the sender. Called by the holder.
+fn update_operator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operator: String,
+    expires: Option<Expiration>,
+    approve: bool,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
+    deps.api.addr_validate(&operator)?;
+
+    let key = (info.sender.as_str(), operator.as_str());
+    if approve {
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+        NFT_OPERATORS.save(deps.storage, key, &expires)?;
+    } else {
+        NFT_OPERATORS.remove(deps.storage, key);
     }
 
-    #[test]
-    fn register_vesting_account_linear_invalid_request() {
-        // Mock dependencies
-        let mut env = mock_env();
-        let mut deps = mock_dependencies();
+    Ok(Response::new().add_attributes(vec![
+        ("action", if approve { "approve_all" } else { "revoke_all" }),
+        ("sender", info.sender.as_str()),
+        ("operator", operator.as_str()),
+    ]))
+}
 
-        // vesting details
-        let vesting_amount = 1000;
-        let address = Addr::unchecked("user1");
-        let vesting_schedule = VestingSchedule::LinearVesting {
-            start_time: 5000,
-            end_time: 6000,
-            vesting_amount: Uint128::from(vesting_amount),
-        };
+/// Cancels the grant behind `token_id` on behalf of its holder, whose claim recipient
+/// gets the vested amount. Called by the holder, an approved spender or an operator.
+fn burn_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
 
-        let msg = ExecuteMsg::RegisterVestingAccount {
-            master_address: String::new(),
-            address: address.to_string(),
-            vesting_schedule,
-        };
+    let account = load_token(deps.as_ref(), &token_id)?;
+    assert_can_send(deps.as_ref(), &env, &info.sender, &account)?;
+
+    // spenders and operators never choose where the tokens go
+    let address = account.address.clone();
+    let denom = account.vesting_denom.clone();
+    let recipient = claim_recipient(deps.as_ref(), address.clone())?.recipient;
+    let response = deregister_vesting_account(
+        deps,
+        env,
+        &address,
+        &denom,
+        vec![account],
+        recipient,
+        "burn",
+    )?;
 
-        let info = mock_info(address.as_str(), &coins(vesting_amount, DENOM));
+    Ok(response.add_attributes(vec![
+        ("sender", info.sender.as_str()),
+        ("token_id", token_id.as_str()),
+    ]))
+}
 
-        // * FAIL for empty master address
-        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
-        match res {
-            StdError::GenericErr { .. } => {}
-            e => panic!("{:?}", e),
-        };
+/// Revokes grants of `address` funded by the sender. Called by the master.
+fn revoke_vesting_account(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+    denom: Denom,
+    grant_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
+    let sender = info.sender;
+    deps.api.addr_validate(&address)?;
 
-        // * FAIL for start_time < block_time
-        let vesting_schedule = VestingSchedule::LinearVesting {
-            start_time: 5000,
-            end_time: 6000,
-            vesting_amount: Uint128::from(vesting_amount),
-        };
-        let msg = ExecuteMsg::RegisterVestingAccount {
-            master_address: String::from("master"),
-            address: address.to_string(),
-            vesting_schedule,
-        };
+    // vesting_account existence check
+    let accounts = load_grants(deps.as_ref(), &address, &denom, grant_id)?;
+
+    // only the master who funded a grant may revoke it
+    let accounts: Vec<VestingAccount> = accounts
+        .into_iter()
+        .filter(|account| account.master_address == sender)
+        .collect();
+    if accounts.is_empty() {
+        return Err(ContractError::Unauthorized {});
+    }
 
-        env.block.time = Timestamp::from_seconds(6000);
+    // irrevocable grants can never be clawed back
+    let accounts: Vec<VestingAccount> = accounts
+        .into_iter()
+        .filter(|account| account.revocable)
+        .collect();
+    if accounts.is_empty() {
+        return Err(ContractError::NotRevocable {
+            denom: denom_to_key(&denom),
+            grant_id,
+        });
+    }
 
-        let result = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
-        match result {
-            StdError::GenericErr { msg }
-                if msg == String::from("assert(start_time < block_time)") => {}
-            e => panic!("{:?}", e),
-        };
+    deregister_vesting_account(
+        deps,
+        env,
+        &address,
+        &denom,
+        accounts,
+        address.clone(),
+        "revoke_vesting_account",
+    )
+}
 
-        // * FAIL for start_time == end_time
-        let vesting_schedule = VestingSchedule::LinearVesting {
-            start_time: 6000,
-            end_time: 6000,
-            vesting_amount: Uint128::from(vesting_amount),
-        };
+/// Removes the given grants of `address`, sending the vested but unclaimed
+/// amount to `vested_token_recipient` and the unvested amount back to the master.
+fn deregister_vesting_account(
+    deps: DepsMut,
+    env: Env,
+    address: &str,
+    denom: &Denom,
+    accounts: Vec<VestingAccount>,
+    vested_token_recipient: String,
+    action: &str,
+) -> Result<Response, ContractError> {
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut attrs: Vec<Attribute> = vec![];
 
-        let msg = ExecuteMsg::RegisterVestingAccount {
-            master_address: String::from("master"),
-            address: address.to_string(),
-            vesting_schedule,
-        };
+    let denom_key = denom_to_key(denom);
+    let mut stats = DENOM_STATS.load(deps.storage, &denom_key)?;
+    for account in accounts {
+        // remove vesting account
+        vesting_accounts().remove(deps.storage, (address, &denom_key, account.grant_id))?;
 
-        let result = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
-        match result {
-            StdError::GenericErr { msg }
-                if msg == String::from("assert(end_time <= start_time)") => {}
-            e => panic!("{:?}", e),
-        };
+        // a split grant may have claimed slightly ahead of its own schedule
+        let claimed_amount = account.claimed_amount;
+        let vested_amount = account
+            .vesting_schedule
+            .vested_amount(env.block.time.seconds())?
+            .max(claimed_amount);
 
-        // * FAIL for empty `address` field.
-        let vesting_schedule = VestingSchedule::LinearVesting {
-            start_time: 6000,
-            end_time: 8000,
-            vesting_amount: Uint128::from(vesting_amount),
-        };
+        // transfer already vested but not claimed amount to
+        // the `vested_token_recipient` address
+        let claimable_amount = vested_amount.checked_sub(claimed_amount)?;
+        if !claimable_amount.is_zero() {
+            let message = transfer_msg(
+                &account.vesting_denom,
+                vested_token_recipient.clone(),
+                claimable_amount,
+            )?;
+
+            messages.push(message);
+        }
 
-        let msg = ExecuteMsg::RegisterVestingAccount {
-            master_address: String::from("master"),
-            address: String::new(),
-            vesting_schedule,
-        };
+        // transfer left vesting amount to the master address
+        let left_vesting_amount = account.vesting_amount.checked_sub(vested_amount)?;
+        if !left_vesting_amount.is_zero() {
+            let master_account = account.master_address;
+            deps.api.addr_validate(&master_account)?;
+            let message =
+                transfer_msg(&account.vesting_denom, master_account, left_vesting_amount)?;
 
-        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            messages.push(message);
+        }
+
+        stats.claimed = stats.claimed.checked_add(claimable_amount)?;
+        stats.returned = stats.returned.checked_add(left_vesting_amount)?;
+        stats.outstanding = stats
+            .outstanding
+            .checked_sub(claimable_amount + left_vesting_amount)?;
+        stats.active_grants -= 1;
+        attrs.extend(vec![
+            Attribute::new("grant_id", account.grant_id.to_string()),
+            Attribute::new("vesting_denom", to_string(&account.vesting_denom).unwrap()),
+            Attribute::new("vesting_amount", account.vesting_amount.to_string()),
+            Attribute::new("vested_amount", vested_amount.to_string()),
+            Attribute::new("left_vesting_amount", left_vesting_amount.to_string()),
+        ]);
     }
 
-    #[test]
-    fn register_vesting_account_periodic_invalid_request() {
-        // Mock dependencies
-        let mut env = mock_env();
-        let mut deps = mock_dependencies();
+    DENOM_STATS.save(deps.storage, &denom_key, &stats)?;
 
-        env.block.time = Timestamp::from_seconds(200);
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![("action", action), ("address", address)])
+        .add_attributes(attrs))
+}
 
-        // vesting details
-        let vesting_amount = 1000;
-        let address = Addr::unchecked("user1");
-        let vesting_schedule = VestingSchedule::PeriodicVesting {
-            start_time: 1000,
+fn claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denoms: Vec<Denom>,
+    grant_id: Option<u64>,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
+
+    let sender = info.sender;
+    let recipient = match recipient {
+        Some(recipient) => recipient,
+        None => claim_recipient(deps.as_ref(), sender.to_string())?.recipient,
+    };
+    deps.api.addr_validate(&recipient)?;
+
+    let (messages, attrs) =
+        claim_grants(deps, &env, sender.as_str(), &denoms, grant_id, recipient)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![("action", "claim"), ("address", sender.as_str())])
+        .add_attributes(attrs))
+}
+
+/// Claims the grants of `owner` on their behalf. Called by an approved operator.
+fn claim_for(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    denoms: Vec<Denom>,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
+    deps.api.addr_validate(&owner)?;
+
+    for denom in denoms.iter() {
+        let expires = CLAIM_OPERATORS.may_load(
+            deps.storage,
+            (owner.as_str(), info.sender.as_str(), &denom_to_key(denom)),
+        )?;
+        match expires {
+            Some(expires) if !expires.is_expired(&env.block) => {}
+            _ => return Err(ContractError::Unauthorized {}),
+        }
+    }
+
+    // operators never choose where the tokens go
+    let recipient = claim_recipient(deps.as_ref(), owner.clone())?.recipient;
+    let (messages, attrs) = claim_grants(deps, &env, &owner, &denoms, None, recipient)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            ("action", "claim_for"),
+            ("address", owner.as_str()),
+            ("operator", info.sender.as_str()),
+        ])
+        .add_attributes(attrs))
+}
+
+/// Claims the grants of `address` for the given denoms, or only the given grant
+/// when `grant_id` is set, paying out to `recipient`.
+fn claim_grants(
+    mut deps: DepsMut,
+    env: &Env,
+    address: &str,
+    denoms: &[Denom],
+    grant_id: Option<u64>,
+    recipient: String,
+) -> Result<(Vec<CosmosMsg>, Vec<Attribute>), ContractError> {
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut attrs: Vec<Attribute> = vec![];
+    for denom in denoms.iter() {
+        // vesting_account existence check
+        let accounts = load_grants(deps.as_ref(), address, denom, grant_id)?;
+
+        for account in accounts {
+            let (vested_amount, claimable_amount) =
+                claim_vested(deps.branch(), env, address, &account)?;
+            if claimable_amount.is_zero() {
+                continue;
+            }
+
+            let message =
+                transfer_msg(&account.vesting_denom, recipient.clone(), claimable_amount)?;
+            messages.push(message);
+            attrs.extend(claim_attributes(&account, vested_amount, claimable_amount));
+        }
+    }
+
+    Ok((messages, attrs))
+}
+
+fn set_auto_distribution(
+    deps: DepsMut,
+    info: MessageInfo,
+    enabled: bool,
+    max_bounty: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
+
+    // the accepted share is kept, so raising the bounty later never applies to the sender
+    let max_bounty = match max_bounty {
+        Some(max_bounty) => max_bounty,
+        None => CONFIG.load(deps.storage)?.distribution_bounty,
+    };
+    assert_distribution_bounty(max_bounty)?;
+    if enabled {
+        AUTO_DISTRIBUTION.save(deps.storage, info.sender.as_str(), &max_bounty)?;
+    } else {
+        AUTO_DISTRIBUTION.remove(deps.storage, info.sender.as_str());
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "set_auto_distribution"),
+        ("address", info.sender.as_str()),
+        ("enabled", &enabled.to_string()),
+        ("max_bounty", &max_bounty.to_string()),
+    ]))
+}
+
+/// Pays out the vested tokens of opted-in beneficiaries, visiting grants after the
+/// `(address, denom, grant_id)` cursor. Anyone can call it; the sender earns the bounty.
+fn distribute_vested(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    start_after: Option<(String, Denom, u64)>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
+    let config = CONFIG.load(deps.storage)?;
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after =
+        start_after.map(|(address, denom, grant_id)| (address, denom_to_key(&denom), grant_id));
+    let accounts = vesting_accounts()
+        .range(
+            deps.storage,
+            start_after.as_ref().map(|(address, denom, grant_id)| {
+                Bound::exclusive((address.as_str(), denom.as_str(), *grant_id))
+            }),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, account)| account))
+        .collect::<StdResult<Vec<VestingAccount>>>()?;
+
+    let mut bounties: Vec<(Denom, Uint128)> = vec![];
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut attrs: Vec<Attribute> = vec![];
+    for account in accounts.iter() {
+        let max_bounty = match AUTO_DISTRIBUTION.may_load(deps.storage, &account.address)? {
+            Some(max_bounty) => max_bounty,
+            None => continue,
+        };
+        let (vested_amount, claimable_amount) =
+            claim_vested(deps.branch(), &env, &account.address, account)?;
+        if claimable_amount.is_zero() {
+            continue;
+        }
+
+        let bounty = claimable_amount.mul_floor(config.distribution_bounty.min(max_bounty));
+        let recipient = claim_recipient(deps.as_ref(), account.address.clone())?.recipient;
+        messages.push(transfer_msg(
+            &account.vesting_denom,
+            recipient,
+            claimable_amount.checked_sub(bounty)?,
+        )?);
+        if !bounty.is_zero() {
+            match bounties
+                .iter_mut()
+                .find(|(denom, _)| denom == &account.vesting_denom)
+            {
+                Some((_, amount)) => *amount = amount.checked_add(bounty)?,
+                None => bounties.push((account.vesting_denom.clone(), bounty)),
+            }
+        }
+        attrs.push(Attribute::new("address", account.address.clone()));
+        attrs.extend(claim_attributes(account, vested_amount, claimable_amount));
+        attrs.push(Attribute::new("bounty", bounty.to_string()));
+    }
+    for (denom, amount) in bounties {
+        messages.push(transfer_msg(&denom, info.sender.to_string(), amount)?);
+    }
+
+    let mut response = Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            ("action", "distribute_vested"),
+            ("keeper", info.sender.as_str()),
+        ])
+        .add_attributes(attrs);
+    // more grants may follow a full page, hand out the cursor to continue from
+    if let Some(last) = accounts.last().filter(|_| accounts.len() == limit) {
+        response = response.add_attribute(
+            "next_start_after",
+            to_string(&(&last.address, &last.vesting_denom, last.grant_id)).unwrap(),
+        );
+    }
+
+    Ok(response)
+}
+
+/// Adds or removes a claim operator of the sender for `denom`. Called by the beneficiary.
+fn update_claim_operator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operator: String,
+    denom: Denom,
+    expires: Option<Expiration>,
+    approve: bool,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
+    deps.api.addr_validate(&operator)?;
+
+    let denom_key = denom_to_key(&denom);
+    let key = (info.sender.as_str(), operator.as_str(), denom_key.as_str());
+    if approve {
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired {});
+        }
+        CLAIM_OPERATORS.save(deps.storage, key, &expires)?;
+    } else {
+        CLAIM_OPERATORS.remove(deps.storage, key);
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        (
+            "action",
+            if approve {
+                "approve_claim_operator"
+            } else {
+                "revoke_claim_operator"
+            },
+        ),
+        ("address", info.sender.as_str()),
+        ("operator", operator.as_str()),
+        ("vesting_denom", &to_string(&denom).unwrap()),
+    ]))
+}
+
+fn set_claim_recipient(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
+
+    match &recipient {
+        Some(recipient) => {
+            deps.api.addr_validate(recipient)?;
+            CLAIM_RECIPIENTS.save(deps.storage, info.sender.as_str(), recipient)?;
+        }
+        None => CLAIM_RECIPIENTS.remove(deps.storage, info.sender.as_str()),
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "set_claim_recipient"),
+        ("address", info.sender.as_str()),
+        (
+            "recipient",
+            recipient.as_deref().unwrap_or_else(|| info.sender.as_str()),
+        ),
+    ]))
+}
+
+/// Claims every grant of the sender, starting after the `(denom, grant_id)` cursor.
+/// Native tokens are paid out in a single multi-coin bank send.
+fn claim_all(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+    start_after: Option<(Denom, u64)>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsNotAllowed {});
+    };
+
+    let sender = info.sender;
+    let recipient = match recipient {
+        Some(recipient) => recipient,
+        None => claim_recipient(deps.as_ref(), sender.to_string())?.recipient,
+    };
+    deps.api.addr_validate(&recipient)?;
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.map(|(denom, grant_id)| (denom_to_key(&denom), grant_id));
+    let accounts = vesting_accounts()
+        .sub_prefix(sender.as_str())
+        .range(
+            deps.storage,
+            start_after
+                .as_ref()
+                .map(|(denom, grant_id)| Bound::exclusive((denom.as_str(), *grant_id))),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, account)| account))
+        .collect::<StdResult<Vec<VestingAccount>>>()?;
+
+    let mut coins: Vec<Coin> = vec![];
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut attrs: Vec<Attribute> = vec![];
+    for account in accounts.iter() {
+        let (vested_amount, claimable_amount) =
+            claim_vested(deps.branch(), &env, sender.as_str(), account)?;
+        if claimable_amount.is_zero() {
+            continue;
+        }
+
+        match &account.vesting_denom {
+            Denom::Native(denom) => match coins.iter_mut().find(|coin| &coin.denom == denom) {
+                Some(coin) => coin.amount = coin.amount.checked_add(claimable_amount)?,
+                None => coins.push(Coin {
+                    denom: denom.clone(),
+                    amount: claimable_amount,
+                }),
+            },
+            denom => messages.push(transfer_msg(denom, recipient.clone(), claimable_amount)?),
+        }
+        attrs.extend(claim_attributes(account, vested_amount, claimable_amount));
+    }
+
+    if !coins.is_empty() {
+        coins.sort_by(|a, b| a.denom.cmp(&b.denom));
+        messages.insert(
+            0,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient,
+                amount: coins,
+            }),
+        );
+    }
+
+    let mut response = Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![("action", "claim_all"), ("address", sender.as_str())])
+        .add_attributes(attrs);
+    // more grants may follow a full page, hand out the cursor to continue from
+    if let Some(last) = accounts.last().filter(|_| accounts.len() == limit) {
+        response = response.add_attribute(
+            "next_start_after",
+            to_string(&(&last.vesting_denom, last.grant_id)).unwrap(),
+        );
+    }
+
+    Ok(response)
+}
+
+/// Marks everything vested so far as claimed, removing the grant once it is fully claimed.
+/// Returns the vested and the newly claimable amount.
+fn claim_vested(
+    deps: DepsMut,
+    env: &Env,
+    address: &str,
+    account: &VestingAccount,
+) -> Result<(Uint128, Uint128), ContractError> {
+    let vested_amount = account
+        .vesting_schedule
+        .vested_amount(env.block.time.seconds())?;
+    // a split grant may have claimed slightly ahead of its own schedule
+    let claimable_amount = vested_amount.saturating_sub(account.claimed_amount);
+    if claimable_amount.is_zero() {
+        return Ok((vested_amount, claimable_amount));
+    }
+
+    let denom_key = denom_to_key(&account.vesting_denom);
+    let key = (address, denom_key.as_str(), account.grant_id);
+    let fully_claimed = vested_amount == account.vesting_amount;
+    if fully_claimed {
+        vesting_accounts().remove(deps.storage, key)?;
+    } else {
+        let mut account = account.clone();
+        account.claimed_amount = vested_amount;
+        vesting_accounts().save(deps.storage, key, &account)?;
+    }
+
+    let mut stats = DENOM_STATS.load(deps.storage, &denom_key)?;
+    stats.claimed = stats.claimed.checked_add(claimable_amount)?;
+    stats.outstanding = stats.outstanding.checked_sub(claimable_amount)?;
+    if fully_claimed {
+        stats.active_grants -= 1;
+    }
+    DENOM_STATS.save(deps.storage, &denom_key, &stats)?;
+
+    Ok((vested_amount, claimable_amount))
+}
+
+fn claim_attributes(
+    account: &VestingAccount,
+    vested_amount: Uint128,
+    claimable_amount: Uint128,
+) -> Vec<Attribute> {
+    vec![
+        Attribute::new("grant_id", account.grant_id.to_string()),
+        Attribute::new("vesting_denom", to_string(&account.vesting_denom).unwrap()),
+        Attribute::new("vesting_amount", account.vesting_amount.to_string()),
+        Attribute::new("vested_amount", vested_amount.to_string()),
+        Attribute::new("claim_amount", claimable_amount.to_string()),
+    ]
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::VestingAccount {
+            address,
+            start_after,
+            limit,
+            at_time,
+        } => to_json_binary(&vesting_account(
+            deps,
+            env,
+            address,
+            start_after,
+            limit,
+            at_time,
+        )?),
+        QueryMsg::VestedTokens { denom } => to_json_binary(&vested_tokens(deps, env, denom)?),
+        QueryMsg::DenomStats { denom } => to_json_binary(&denom_stats(deps, denom)?),
+        QueryMsg::Solvency { denom } => to_json_binary(&solvency(deps, &env, denom)?),
+        QueryMsg::ValidateVestingSchedule { schedule, deposit } => {
+            to_json_binary(&validate_vesting_schedule(&env, schedule, deposit))
+        }
+        QueryMsg::VestingTimeline {
+            address,
+            denom,
+            grant_id,
+            from,
+            to,
+            step,
+        } => to_json_binary(&vesting_timeline(
+            deps, &env, address, denom, grant_id, from, to, step,
+        )?),
+        QueryMsg::AllDenomStats { start_after, limit } => {
+            to_json_binary(&all_denom_stats(deps, start_after, limit)?)
+        }
+        QueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?),
+        QueryMsg::Creators { start_after, limit } => {
+            to_json_binary(&creators(deps, start_after, limit)?)
+        }
+        QueryMsg::Cw20Tokens { start_after, limit } => {
+            to_json_binary(&cw20_tokens(deps, start_after, limit)?)
+        }
+        QueryMsg::VestingAccountsByMaster {
+            master,
+            start_after,
+            limit,
+            at_time,
+        } => to_json_binary(&vesting_accounts_by_master(
+            deps,
+            env,
+            master,
+            start_after,
+            limit,
+            at_time,
+        )?),
+        QueryMsg::AllVestingAccounts {
+            start_after,
+            limit,
+            denom_filter,
+            at_time,
+        } => to_json_binary(&all_vesting_accounts(
+            deps,
+            env,
+            start_after,
+            limit,
+            denom_filter,
+            at_time,
+        )?),
+        QueryMsg::ClaimOperators {
+            owner,
+            start_after,
+            limit,
+        } => to_json_binary(&claim_operators(deps, owner, start_after, limit)?),
+        QueryMsg::ClaimRecipient { owner } => to_json_binary(&claim_recipient(deps, owner)?),
+        QueryMsg::AutoDistribution { owner } => {
+            let max_bounty = AUTO_DISTRIBUTION.may_load(deps.storage, &owner)?;
+            to_json_binary(&AutoDistributionResponse {
+                owner,
+                enabled: max_bounty.is_some(),
+                max_bounty: max_bounty.unwrap_or_default(),
+            })
+        }
+        QueryMsg::OwnerOf {
+            token_id,
+            include_expired,
+        } => to_json_binary(&owner_of(
+            deps,
+            &env,
+            &token_id,
+            include_expired.unwrap_or(false),
+        )?),
+        QueryMsg::NftInfo { token_id } => to_json_binary(&nft_info(deps, &env, &token_id)?),
+        QueryMsg::AllNftInfo {
+            token_id,
+            include_expired,
+        } => to_json_binary(&AllNftInfoResponse {
+            access: owner_of(deps, &env, &token_id, include_expired.unwrap_or(false))?,
+            info: nft_info(deps, &env, &token_id)?,
+        }),
+        QueryMsg::Approval {
+            token_id,
+            spender,
+            include_expired,
+        } => to_json_binary(&approval(
+            deps,
+            &env,
+            &token_id,
+            spender,
+            include_expired.unwrap_or(false),
+        )?),
+        QueryMsg::Approvals {
+            token_id,
+            include_expired,
+        } => to_json_binary(&ApprovalsResponse {
+            approvals: owner_of(deps, &env, &token_id, include_expired.unwrap_or(false))?.approvals,
+        }),
+        QueryMsg::Operator {
+            owner,
+            operator,
+            include_expired,
+        } => to_json_binary(&nft_operator(
+            deps,
+            &env,
+            owner,
+            operator,
+            include_expired.unwrap_or(false),
+        )?),
+        QueryMsg::AllOperators {
+            owner,
+            include_expired,
+            start_after,
+            limit,
+        } => to_json_binary(&all_nft_operators(
+            deps,
+            &env,
+            owner,
+            include_expired.unwrap_or(false),
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Tokens {
+            owner,
+            start_after,
+            limit,
+        } => to_json_binary(&tokens(deps, owner, start_after, limit)?),
+        QueryMsg::AllTokens { start_after, limit } => {
+            to_json_binary(&all_tokens(deps, start_after, limit)?)
+        }
+        QueryMsg::NumTokens {} => to_json_binary(&num_tokens(deps)?),
+        QueryMsg::ContractInfo {} => to_json_binary(&ContractInfoResponse {
+            name: NFT_NAME.to_string(),
+            symbol: NFT_SYMBOL.to_string(),
+        }),
+        QueryMsg::Minter {} => to_json_binary(&MinterResponse { minter: None }),
+    }
+}
+
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_TIMELINE_POINTS: u64 = 100;
+fn creators(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<CreatorsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+
+    let creators = CREATORS
+        .keys(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(CreatorsResponse { creators })
+}
+
+fn cw20_tokens(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Cw20TokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+
+    let tokens = CW20_TOKENS
+        .keys(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(Cw20TokensResponse { tokens })
+}
+
+fn vesting_account(
+    deps: Deps,
+    env: Env,
+    address: String,
+    start_after: Option<(Denom, u64)>,
+    limit: Option<u32>,
+    at_time: Option<u64>,
+) -> StdResult<VestingAccountResponse> {
+    let time = at_time.unwrap_or_else(|| env.block.time.seconds());
+    let mut vestings: Vec<VestingData> = vec![];
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    deps.api.addr_validate(&address)?;
+
+    let start_after = start_after.map(|(denom, grant_id)| (denom_to_key(&denom), grant_id));
+    for item in vesting_accounts()
+        .sub_prefix(address.as_str())
+        .range(
+            deps.storage,
+            start_after
+                .as_ref()
+                .map(|(denom, grant_id)| Bound::exclusive((denom.as_str(), *grant_id))),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+    {
+        let (_, account) = item?;
+        vestings.push(vesting_data(&env, time, account)?);
+    }
+
+    Ok(VestingAccountResponse { address, vestings })
+}
+
+fn vesting_accounts_by_master(
+    deps: Deps,
+    env: Env,
+    master: String,
+    start_after: Option<(String, Denom, u64)>,
+    limit: Option<u32>,
+    at_time: Option<u64>,
+) -> StdResult<VestingAccountsByMasterResponse> {
+    let time = at_time.unwrap_or_else(|| env.block.time.seconds());
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    deps.api.addr_validate(&master)?;
+
+    let start_after =
+        start_after.map(|(address, denom, grant_id)| (address, denom_to_key(&denom), grant_id));
+    let vestings = vesting_accounts()
+        .idx
+        .master
+        .prefix(master.clone())
+        .range(
+            deps.storage,
+            start_after.as_ref().map(|(address, denom, grant_id)| {
+                Bound::exclusive((address.as_str(), denom.as_str(), *grant_id))
+            }),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| vesting_data(&env, time, item?.1))
+        .collect::<StdResult<Vec<VestingData>>>()?;
+
+    Ok(VestingAccountsByMasterResponse { master, vestings })
+}
+
+fn all_vesting_accounts(
+    deps: Deps,
+    env: Env,
+    start_after: Option<(String, Denom, u64)>,
+    limit: Option<u32>,
+    denom_filter: Option<Denom>,
+    at_time: Option<u64>,
+) -> StdResult<AllVestingAccountsResponse> {
+    let time = at_time.unwrap_or_else(|| env.block.time.seconds());
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let start_after =
+        start_after.map(|(address, denom, grant_id)| (address, denom_to_key(&denom), grant_id));
+    let start = start_after.as_ref().map(|(address, denom, grant_id)| {
+        Bound::exclusive((address.as_str(), denom.as_str(), *grant_id))
+    });
+    let accounts = match denom_filter {
+        Some(denom) => vesting_accounts()
+            .idx
+            .denom
+            .prefix(denom_to_key(&denom))
+            .range(deps.storage, start, None, Order::Ascending),
+        None => vesting_accounts().range(deps.storage, start, None, Order::Ascending),
+    };
+    let vestings = accounts
+        .take(limit)
+        .map(|item| vesting_data(&env, time, item?.1))
+        .collect::<StdResult<Vec<VestingData>>>()?;
+
+    Ok(AllVestingAccountsResponse { vestings })
+}
+
+fn claim_operators(
+    deps: Deps,
+    owner: String,
+    start_after: Option<(String, Denom)>,
+    limit: Option<u32>,
+) -> StdResult<ClaimOperatorsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    deps.api.addr_validate(&owner)?;
+
+    let start_after = start_after.map(|(operator, denom)| (operator, denom_to_key(&denom)));
+    let operators = CLAIM_OPERATORS
+        .sub_prefix(owner.as_str())
+        .range(
+            deps.storage,
+            start_after
+                .as_ref()
+                .map(|(operator, denom)| Bound::exclusive((operator.as_str(), denom.as_str()))),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            let ((operator, denom_key), expires) = item?;
+            Ok(ClaimOperator {
+                operator,
+                denom: key_to_denom(&denom_key)?,
+                expires,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ClaimOperatorsResponse { owner, operators })
+}
+
+fn claim_recipient(deps: Deps, owner: String) -> StdResult<ClaimRecipientResponse> {
+    let recipient = CLAIM_RECIPIENTS
+        .may_load(deps.storage, owner.as_str())?
+        .unwrap_or_else(|| owner.clone());
+
+    Ok(ClaimRecipientResponse { owner, recipient })
+}
+
+fn owner_of(
+    deps: Deps,
+    env: &Env,
+    token_id: &str,
+    include_expired: bool,
+) -> StdResult<OwnerOfResponse> {
+    let account = query_token(deps, token_id)?;
+    let approvals = account
+        .approvals
+        .into_iter()
+        .filter(|approval| include_expired || !approval.expires.is_expired(&env.block))
+        .collect();
+
+    Ok(OwnerOfResponse {
+        owner: account.address,
+        approvals,
+    })
+}
+
+fn approval(
+    deps: Deps,
+    env: &Env,
+    token_id: &str,
+    spender: String,
+    include_expired: bool,
+) -> StdResult<ApprovalResponse> {
+    let approval = owner_of(deps, env, token_id, include_expired)?
+        .approvals
+        .into_iter()
+        .find(|approval| approval.spender == spender)
+        .ok_or_else(|| StdError::not_found(format!("approval of {}", spender)))?;
+
+    Ok(ApprovalResponse { approval })
+}
+
+fn nft_operator(
+    deps: Deps,
+    env: &Env,
+    owner: String,
+    operator: String,
+    include_expired: bool,
+) -> StdResult<OperatorResponse> {
+    deps.api.addr_validate(&owner)?;
+    let expires = NFT_OPERATORS
+        .may_load(deps.storage, (owner.as_str(), operator.as_str()))?
+        .filter(|expires| include_expired || !expires.is_expired(&env.block))
+        .ok_or_else(|| StdError::not_found(format!("operator {} of {}", operator, owner)))?;
+
+    Ok(OperatorResponse {
+        approval: Approval {
+            spender: operator,
+            expires,
+        },
+    })
+}
+
+fn all_nft_operators(
+    deps: Deps,
+    env: &Env,
+    owner: String,
+    include_expired: bool,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<OperatorsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    deps.api.addr_validate(&owner)?;
+
+    let operators = NFT_OPERATORS
+        .prefix(owner.as_str())
+        .range(
+            deps.storage,
+            start_after.as_deref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .filter(|item| {
+            item.as_ref().map_or(true, |(_, expires)| {
+                include_expired || !expires.is_expired(&env.block)
+            })
+        })
+        .take(limit)
+        .map(|item| {
+            let (spender, expires) = item?;
+            Ok(Approval { spender, expires })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(OperatorsResponse { operators })
+}
+
+fn nft_info(deps: Deps, env: &Env, token_id: &str) -> StdResult<NftInfoResponse> {
+    let account = query_token(deps, token_id)?;
+
+    Ok(NftInfoResponse {
+        token_uri: None,
+        extension: vesting_data(env, env.block.time.seconds(), account)?,
+    })
+}
+
+fn tokens(
+    deps: Deps,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    deps.api.addr_validate(&owner)?;
+
+    // the cursor is resolved to the (denom, grant_id) key of the grant
+    let start_after = match start_after {
+        Some(token_id) => {
+            let account = query_token(deps, &token_id)?;
+            if account.address != owner {
+                return Err(StdError::not_found(format!(
+                    "grant {} of {}",
+                    token_id, owner
+                )));
+            }
+            Some((denom_to_key(&account.vesting_denom), account.grant_id))
+        }
+        None => None,
+    };
+    let tokens = vesting_accounts()
+        .sub_prefix(owner.as_str())
+        .keys(
+            deps.storage,
+            start_after
+                .as_ref()
+                .map(|(denom, grant_id)| Bound::exclusive((denom.as_str(), *grant_id))),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, grant_id)| grant_id.to_string()))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(TokensResponse { tokens })
+}
+
+fn all_tokens(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|token_id| {
+            token_id
+                .parse::<u64>()
+                .map_err(|_| StdError::generic_err(format!("invalid token id {}", token_id)))
+        })
+        .transpose()?;
+
+    let tokens = vesting_accounts()
+        .idx
+        .grant
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, account)| account.grant_id.to_string()))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(TokensResponse { tokens })
+}
+
+fn num_tokens(deps: Deps) -> StdResult<NumTokensResponse> {
+    let count = DENOM_STATS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, stats)| stats.active_grants))
+        .sum::<StdResult<u64>>()?;
+
+    Ok(NumTokensResponse { count })
+}
+
+/// Vesting details of `account` at `time`. Claims are only recorded as a running total,
+/// so nothing is reported as claimable at a time before the block time.
+fn vesting_data(env: &Env, time: u64, account: VestingAccount) -> StdResult<VestingData> {
+    let vested_amount = account.vesting_schedule.vested_amount(time)?;
+    let claimable_amount = if time < env.block.time.seconds() {
+        Uint128::zero()
+    } else {
+        vested_amount.saturating_sub(account.claimed_amount)
+    };
+
+    Ok(VestingData {
+        grant_id: account.grant_id,
+        master_address: account.master_address,
+        address: account.address,
+        vesting_denom: account.vesting_denom,
+        vesting_amount: account.vesting_amount,
+        vested_amount,
+        vesting_schedule: account.vesting_schedule,
+        claimable_amount,
+        revocable: account.revocable,
+        transfer_policy: account.transfer_policy,
+        pending_transfer: account.pending_transfer,
+    })
+}
+
+fn vested_tokens(deps: Deps, _env: Env, denom: Denom) -> StdResult<Uint128> {
+    let stats = DENOM_STATS
+        .may_load(deps.storage, &denom_to_key(&denom))?
+        .unwrap_or_default();
+    Ok(stats.outstanding)
+}
+
+/// Projects the vested and claimable amounts of the grants of `address` in `denom`
+/// at `from`, every `step` seconds after it, and at `to`. Claims are only recorded as a
+/// running total, so nothing is claimable at points before the block time.
+#[allow(clippy::too_many_arguments)]
+fn vesting_timeline(
+    deps: Deps,
+    env: &Env,
+    address: String,
+    denom: Denom,
+    grant_id: Option<u64>,
+    from: u64,
+    to: u64,
+    step: u64,
+) -> StdResult<VestingTimelineResponse> {
+    deps.api.addr_validate(&address)?;
+    if step == 0 {
+        return Err(StdError::generic_err("step must not be zero"));
+    }
+    if to < from {
+        return Err(StdError::generic_err("to must not be before from"));
+    }
+    // the points every `step` from `from`, plus `to` if it falls between two of them
+    let span = to - from;
+    let point_count = span.div_ceil(step) + 1;
+    if point_count > MAX_TIMELINE_POINTS {
+        return Err(StdError::generic_err(format!(
+            "timeline must not have more than {} points",
+            MAX_TIMELINE_POINTS
+        )));
+    }
+
+    let accounts = may_load_grants(deps, &address, &denom, grant_id)?;
+    // step in u64, `usize` is only 32 bits wide on wasm32
+    let mut times: Vec<u64> = vec![from];
+    let mut time = from;
+    while let Some(next) = time.checked_add(step).filter(|next| *next < to) {
+        times.push(next);
+        time = next;
+    }
+    if time != to {
+        times.push(to);
+    }
+
+    let mut points: Vec<TimelinePoint> = vec![];
+    for time in times {
+        let mut vested_amount = Uint128::zero();
+        let mut claimable_amount = Uint128::zero();
+        for account in accounts.iter() {
+            let vested = account.vesting_schedule.vested_amount(time)?;
+            vested_amount = vested_amount.checked_add(vested)?;
+            if time >= env.block.time.seconds() {
+                claimable_amount =
+                    claimable_amount.checked_add(vested.saturating_sub(account.claimed_amount))?;
+            }
+        }
+        points.push(TimelinePoint {
+            time,
+            vested_amount,
+            claimable_amount,
+        });
+    }
+
+    Ok(VestingTimelineResponse {
+        address,
+        denom,
+        points,
+    })
+}
+
+/// Runs the registration checks of `schedule` against a deposit of `deposit`.
+fn validate_vesting_schedule(
+    env: &Env,
+    schedule: VestingSchedule,
+    deposit: Uint128,
+) -> ValidateVestingScheduleResponse {
+    match schedule.validate(env.block.time.seconds(), deposit) {
+        Ok(vesting_amount) => ValidateVestingScheduleResponse::Valid {
+            schedule,
+            vesting_amount,
+        },
+        Err(error) => ValidateVestingScheduleResponse::Invalid { error },
+    }
+}
+
+/// Compares the outstanding amount of `denom` with the balance held by the contract.
+fn solvency(deps: Deps, env: &Env, denom: Denom) -> StdResult<SolvencyResponse> {
+    let outstanding = DENOM_STATS
+        .may_load(deps.storage, &denom_to_key(&denom))?
+        .unwrap_or_default()
+        .outstanding;
+    let balance = match &denom {
+        Denom::Native(denom) => {
+            deps.querier
+                .query_balance(&env.contract.address, denom)?
+                .amount
+        }
+        Denom::Cw20(contract_addr) => {
+            let response: Cw20BalanceResponse = deps.querier.query_wasm_smart(
+                contract_addr,
+                &Cw20QueryMsg::Balance {
+                    address: env.contract.address.to_string(),
+                },
+            )?;
+            response.balance
+        }
+    };
+
+    Ok(SolvencyResponse {
+        denom,
+        outstanding,
+        balance,
+        surplus: balance.saturating_sub(outstanding),
+        deficit: outstanding.saturating_sub(balance),
+    })
+}
+
+fn denom_stats(deps: Deps, denom: Denom) -> StdResult<DenomStatsResponse> {
+    let stats = DENOM_STATS
+        .may_load(deps.storage, &denom_to_key(&denom))?
+        .unwrap_or_default();
+    Ok(DenomStatsResponse { denom, stats })
+}
+
+fn all_denom_stats(
+    deps: Deps,
+    start_after: Option<Denom>,
+    limit: Option<u32>,
+) -> StdResult<AllDenomStatsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.as_ref().map(denom_to_key);
+    let stats = DENOM_STATS
+        .range(
+            deps.storage,
+            start_after.as_deref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| {
+            let (key, stats) = item?;
+            Ok(DenomStatsResponse {
+                denom: key_to_denom(&key)?,
+                stats,
+            })
+        })
+        .collect::<StdResult<Vec<DenomStatsResponse>>>()?;
+
+    Ok(AllDenomStatsResponse { stats })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, _env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    match msg {
+        SudoMsg::UpdateVestingContract { address } => {
+            config.vesting_contract = Some(address);
+        }
+        SudoMsg::UpdateConfig { owner, app_id } => {
+            if let Some(owner) = owner {
+                config.owner = owner;
+            }
+            if let Some(app_id) = app_id {
+                config.app_id = app_id;
+            }
+        }
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "sudo_update_config"),
+        ("owner", config.owner.as_str()),
+        ("app_id", &config.app_id.to_string()),
+    ]))
+}
+
+#[entry_point]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let ver = cw2::get_contract_version(deps.storage)?;
+    // ensure we are migrating from an allowed contract
+    if ver.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidMigrationContract {
+            contract: ver.contract,
+        });
+    }
+    // note: better to do proper semver compare, but string compare *usually* works
+    if ver.version.as_str() > CONTRACT_VERSION {
+        return Err(ContractError::InvalidMigrationVersion {
+            version: ver.version,
+        });
+    }
+
+    // set the new version
+    cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // versions before the configuration was introduced only stored the app id
+    if CONFIG.may_load(deps.storage)?.is_none() {
+        let owner = msg.owner.ok_or(ContractError::MissingMigrationOwner {})?;
+        let app_id = match msg.app_id {
+            Some(app_id) => app_id,
+            None => LEGACY_APP_ID.may_load(deps.storage)?.unwrap_or_default(),
+        };
+        // registration was permissionless before the creator allowlist
+        CONFIG.save(
+            deps.storage,
+            &Config {
+                owner: deps.api.addr_validate(&owner)?,
+                app_id,
+                vesting_contract: None,
+                open_registration: true,
+                distribution_bounty: Decimal::zero(),
+            },
+        )?;
+        LEGACY_APP_ID.remove(deps.storage);
+        migrate_legacy_grants(deps.storage)?;
+    }
+
+    Ok(Response::default())
+}
+
+/// Re-keys the grants of the legacy layout, stored under `(address, native denom)`,
+/// to `(address, denom key, grant id)` with a freshly assigned grant id, and rebuilds
+/// the denom stats from them. Grants paid out or deregistered before the migration
+/// are no longer known and are not part of the stats.
+fn migrate_legacy_grants(storage: &mut dyn Storage) -> StdResult<()> {
+    let legacy_accounts = LEGACY_VESTING_ACCOUNTS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // both layouts share a namespace, so drop every legacy row before saving grants
+    for ((address, denom), _) in legacy_accounts.iter() {
+        LEGACY_VESTING_ACCOUNTS.remove(storage, (address, denom));
+    }
+
+    for (_, legacy) in legacy_accounts {
+        let vesting_denom = Denom::Native(legacy.vesting_denom);
+        let account = VestingAccount {
+            grant_id: next_grant_id(storage)?,
+            master_address: legacy.master_address,
+            address: legacy.address,
+            vesting_denom,
+            vesting_amount: legacy.vesting_amount,
+            vesting_schedule: legacy.vesting_schedule,
+            claimed_amount: legacy.claimed_amount,
+            // masters could never claw back legacy grants
+            revocable: false,
+            transfer_policy: TransferPolicy::default(),
+            pending_transfer: None,
+            approvals: vec![],
+        };
+        let denom_key = denom_to_key(&account.vesting_denom);
+        vesting_accounts().save(
+            storage,
+            (&account.address, &denom_key, account.grant_id),
+            &account,
+        )?;
+
+        let mut stats = DENOM_STATS
+            .may_load(storage, &denom_key)?
+            .unwrap_or_default();
+        stats.deposited = stats.deposited.checked_add(account.vesting_amount)?;
+        stats.claimed = stats.claimed.checked_add(account.claimed_amount)?;
+        stats.outstanding = stats
+            .outstanding
+            .checked_add(account.vesting_amount.checked_sub(account.claimed_amount)?)?;
+        stats.active_grants += 1;
+        DENOM_STATS.save(storage, &denom_key, &stats)?;
+    }
+
+    // the legacy totals never accounted for claims, so they are not carried over
+    let legacy_denoms = LEGACY_VESTED_BY_DENOM
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for denom in legacy_denoms {
+        LEGACY_VESTED_BY_DENOM.remove(storage, &denom);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ScheduleError;
+    use crate::state::{DenomStats, LegacyVestingAccount};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR};
+    use cosmwasm_std::{
+        coin, coins, Addr, BankMsg, ContractResult, CosmosMsg, Decimal, StdError, SystemResult,
+        Timestamp, WasmQuery,
+    };
+
+    const DENOM: &str = "TKN";
+    const DENOM_KEY: &str = "native-TKN";
+
+    fn instantiate_contract(deps: DepsMut) {
+        let msg = InstantiateMsg {
+            owner: Some("owner".to_string()),
+            app_id: 1,
+            vesting_contract: None,
+            open_registration: true,
+            distribution_bounty: None,
+        };
+        instantiate(deps, mock_env(), mock_info("creator", &[]), msg).unwrap();
+    }
+
+    fn accept_cw20_token(deps: DepsMut, token: &str) {
+        let msg = ExecuteMsg::AddCw20Tokens {
+            tokens: vec![token.to_string()],
+        };
+        execute(deps, mock_env(), mock_info("owner", &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn proper_initialization() {
+        let env = mock_env();
+        let mut deps = mock_dependencies();
+        let info = mock_info("sender", &coins(0, DENOM.to_string()));
+
+        let msg = InstantiateMsg {
+            owner: None,
+            app_id: 1,
+            vesting_contract: None,
+            open_registration: true,
+            distribution_bounty: None,
+        };
+        let res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(res.messages.len(), 0);
+        assert_eq!(res.attributes.len(), 0);
+
+        let config: Config =
+            from_json(query(deps.as_ref(), env, QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(
+            config,
+            Config {
+                owner: info.sender,
+                app_id: 1,
+                vesting_contract: None,
+                open_registration: true,
+                distribution_bounty: Decimal::zero(),
+            }
+        );
+    }
+
+    #[test]
+    fn update_config_and_sudo() {
+        let env = mock_env();
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some("owner".to_string()),
+            app_id: 1,
+            vesting_contract: None,
+            open_registration: true,
+            distribution_bounty: None,
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateConfig {
+            owner: Some("new_owner".to_string()),
+            app_id: Some(2),
+            vesting_contract: Some("vesting".to_string()),
+            open_registration: None,
+            distribution_bounty: None,
+        };
+
+        // * FAIL: only the owner can update the config
+        let info = mock_info("creator", &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        match result {
+            ContractError::Unauthorized {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        let info = mock_info("owner", &[]);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(
+            CONFIG.load(deps.as_ref().storage).unwrap(),
+            Config {
+                owner: Addr::unchecked("new_owner"),
+                app_id: 2,
+                vesting_contract: Some(Addr::unchecked("vesting")),
+                open_registration: true,
+                distribution_bounty: Decimal::zero(),
+            }
+        );
+
+        // Chain governance can update the config through sudo
+        sudo(
+            deps.as_mut(),
+            env.clone(),
+            SudoMsg::UpdateVestingContract {
+                address: Addr::unchecked("other_vesting"),
+            },
+        )
+        .unwrap();
+        sudo(
+            deps.as_mut(),
+            env,
+            SudoMsg::UpdateConfig {
+                owner: Some(Addr::unchecked("gov_owner")),
+                app_id: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            CONFIG.load(deps.as_ref().storage).unwrap(),
+            Config {
+                owner: Addr::unchecked("gov_owner"),
+                app_id: 2,
+                vesting_contract: Some(Addr::unchecked("other_vesting")),
+                open_registration: true,
+                distribution_bounty: Decimal::zero(),
+            }
+        );
+    }
+
+    #[test]
+    fn creator_allowlist() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(1000);
+
+        // Restrict registration to allowlisted creators
+        let msg = ExecuteMsg::UpdateConfig {
+            owner: None,
+            app_id: None,
+            vesting_contract: None,
+            open_registration: Some(false),
+            distribution_bounty: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+        let register = ExecuteMsg::RegisterVestingAccount {
+            master_address: "master".to_string(),
+            address: "user1".to_string(),
+            vesting_schedule: VestingSchedule::LinearVesting {
+                start_time: 1000,
+                end_time: 2000,
+                vesting_amount: Uint128::new(1000),
+            },
+            revocable: true,
+            transfer_policy: None,
+        };
+
+        // * FAIL: creator is not allowlisted
+        let info = mock_info("master", &coins(1000, DENOM));
+        let result =
+            execute(deps.as_mut(), env.clone(), info.clone(), register.clone()).unwrap_err();
+        match result {
+            ContractError::Unauthorized {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: only the owner manages the allowlist
+        let msg = ExecuteMsg::AddCreators {
+            creators: vec!["master".to_string(), "treasury".to_string()],
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("master", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+        execute(deps.as_mut(), env.clone(), info.clone(), register.clone()).unwrap();
+
+        let res: CreatorsResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Creators {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.creators,
+            vec![Addr::unchecked("master"), Addr::unchecked("treasury")]
+        );
+        let res = creators(deps.as_ref(), Some("master".to_string()), None).unwrap();
+        assert_eq!(res.creators, vec![Addr::unchecked("treasury")]);
+
+        let msg = ExecuteMsg::RemoveCreators {
+            creators: vec!["master".to_string()],
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+        execute(deps.as_mut(), env, info, register).unwrap_err();
+    }
+
+    #[test]
+    fn register_vesting_account_linear_invalid_request() {
+        // Mock dependencies
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        // vesting details
+        let vesting_amount = 1000;
+        let address = Addr::unchecked("user1");
+        let vesting_schedule = VestingSchedule::LinearVesting {
+            start_time: 5000,
+            end_time: 6000,
+            vesting_amount: Uint128::from(vesting_amount),
+        };
+
+        let msg = ExecuteMsg::RegisterVestingAccount {
+            master_address: String::new(),
+            address: address.to_string(),
+            vesting_schedule,
+            revocable: true,
+            transfer_policy: None,
+        };
+
+        let info = mock_info(address.as_str(), &coins(vesting_amount, DENOM));
+
+        // * FAIL for empty master address
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        match res {
+            ContractError::Std(StdError::GenericErr { .. }) => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL for start_time < block_time
+        let vesting_schedule = VestingSchedule::LinearVesting {
+            start_time: 5000,
+            end_time: 6000,
+            vesting_amount: Uint128::from(vesting_amount),
+        };
+        let msg = ExecuteMsg::RegisterVestingAccount {
+            master_address: String::from("master"),
+            address: address.to_string(),
+            vesting_schedule,
+            revocable: true,
+            transfer_policy: None,
+        };
+
+        env.block.time = Timestamp::from_seconds(6000);
+
+        let result = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        match result {
+            ContractError::Schedule(ScheduleError::StartTimeInPast { .. }) => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL for start_time == end_time
+        let vesting_schedule = VestingSchedule::LinearVesting {
+            start_time: 6000,
+            end_time: 6000,
+            vesting_amount: Uint128::from(vesting_amount),
+        };
+
+        let msg = ExecuteMsg::RegisterVestingAccount {
+            master_address: String::from("master"),
+            address: address.to_string(),
+            vesting_schedule,
+            revocable: true,
+            transfer_policy: None,
+        };
+
+        let result = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        match result {
+            ContractError::Schedule(ScheduleError::InvalidEndTime { .. }) => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL for empty `address` field.
+        let vesting_schedule = VestingSchedule::LinearVesting {
+            start_time: 6000,
+            end_time: 8000,
+            vesting_amount: Uint128::from(vesting_amount),
+        };
+
+        let msg = ExecuteMsg::RegisterVestingAccount {
+            master_address: String::from("master"),
+            address: String::new(),
+            vesting_schedule,
+            revocable: true,
+            transfer_policy: None,
+        };
+
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    }
+
+    #[test]
+    fn register_vesting_account_periodic_invalid_request() {
+        // Mock dependencies
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(200);
+
+        // vesting details
+        let vesting_amount = 1000;
+        let address = Addr::unchecked("user1");
+        let vesting_schedule = VestingSchedule::PeriodicVesting {
+            start_time: 1000,
+            end_time: 5000,
+            vesting_interval: 1000,
+            amount: Uint128::from(vesting_amount),
+        };
+
+        // * FAIL for sending excess amount
+        let deposit_amount = 4000 / 1000 * vesting_amount + 1000;
+        let info = mock_info(address.as_str(), &coins(deposit_amount, DENOM));
+        let msg = ExecuteMsg::RegisterVestingAccount {
+            master_address: "master".to_string(),
+            address: address.to_string(),
+            vesting_schedule: vesting_schedule.clone(),
+            revocable: true,
+            transfer_policy: None,
+        };
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match result {
+            ContractError::Schedule(ScheduleError::DepositMismatch {
+                deposit_amount,
+                vesting_amount,
+            }) if deposit_amount == Uint128::new(5000) && vesting_amount == Uint128::new(4000) => {}
+            e => panic!("{:?}", e),
+        };
+    }
+
+    #[test]
+    fn register_vesting_account_periodic_valid_request() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(200);
+
+        // vesting details
+        let vesting_amount = 1000;
+        let address = Addr::unchecked("user1");
+        let vesting_schedule = VestingSchedule::PeriodicVesting {
+            start_time: 1000,
+            end_time: 5000,
+            vesting_interval: 1000,
+            amount: Uint128::from(vesting_amount),
+        };
+
+        // PASS
+        let deposit_amount = 4000 / 1000 * vesting_amount;
+        let info = mock_info("user1", &coins(deposit_amount, DENOM));
+        let msg = ExecuteMsg::RegisterVestingAccount {
+            master_address: String::from("master"),
+            address: address.to_string(),
+            vesting_schedule: vesting_schedule.clone(),
+            revocable: true,
+            transfer_policy: None,
+        };
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(result.messages.len(), 0);
+
+        // Check correct update in vesting_accounts
+        let vesting_account = vesting_accounts()
+            .load(deps.as_ref().storage, (address.as_str(), DENOM_KEY, 1))
+            .unwrap();
+        assert_eq!(vesting_account.address, address.to_string());
+        assert_eq!(vesting_account.master_address, "master".to_string());
+        assert_eq!(
+            vesting_account.vesting_amount,
+            Uint128::from(deposit_amount)
+        );
+        assert_eq!(vesting_account.claimed_amount, Uint128::zero());
+        assert_eq!(
+            vesting_account.vesting_denom,
+            Denom::Native(DENOM.to_string())
+        );
+        assert_eq!(vesting_account.vesting_schedule, vesting_schedule);
+
+        // Check correct update in DENOM_STATS
+        let denom_vested = DENOM_STATS
+            .load(deps.as_ref().storage, DENOM_KEY)
+            .unwrap()
+            .outstanding;
+        assert_eq!(denom_vested, Uint128::from(deposit_amount));
+    }
+
+    #[test]
+    fn register_vesting_account_linear_valid_request() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(1000);
+
+        // vesting details
+        let vesting_amount = 100;
+        let address = Addr::unchecked("user1");
+        let vesting_schedule = VestingSchedule::LinearVesting {
+            start_time: 1200,
+            end_time: 1500,
+            vesting_amount: Uint128::from(vesting_amount),
+        };
+
+        // PASS
+        let info = mock_info(address.as_str(), &coins(vesting_amount, DENOM));
+        let msg = ExecuteMsg::RegisterVestingAccount {
+            master_address: "master".to_string(),
+            address: info.sender.clone().to_string(),
+            vesting_schedule: vesting_schedule.clone(),
+            revocable: true,
+            transfer_policy: None,
+        };
+
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(res.messages.len(), 0);
+
+        // Check correct update in vesting_accounts
+        let vesting_account = vesting_accounts()
+            .load(deps.as_ref().storage, (address.as_str(), DENOM_KEY, 1))
+            .unwrap();
+        assert_eq!(vesting_account.address, address.to_string());
+        assert_eq!(vesting_account.claimed_amount, Uint128::zero());
+        assert_eq!(vesting_account.master_address, "master".to_string());
+        assert_eq!(
+            vesting_account.vesting_amount,
+            Uint128::from(vesting_amount)
+        );
+        assert_eq!(
+            vesting_account.vesting_denom,
+            Denom::Native(DENOM.to_string())
+        );
+        assert_eq!(vesting_account.vesting_schedule, vesting_schedule);
+
+        // Check correct update in DENOM_STATS
+        let denom_vested = DENOM_STATS
+            .load(deps.as_ref().storage, DENOM_KEY)
+            .unwrap()
+            .outstanding;
+        assert_eq!(denom_vested, Uint128::from(vesting_amount));
+
+        // Should return Response
+        assert_eq!(
+            res,
+            Response::new().add_attributes(vec![
+                ("action", "register_vesting_account"),
+                ("master_address", "master"),
+                ("address", info.sender.as_str()),
+                ("grant_id", "1"),
+                (
+                    "vesting_denom",
+                    &to_string(&Denom::Native(DENOM.to_string())).unwrap()
+                ),
+                ("vesting_amount", &info.funds[0].amount.to_string()),
+                ("revocable", "true"),
+            ])
+        )
+    }
+
+    #[test]
+    fn register_vesting_account_cliff_invalid_request() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(1000);
+
+        let vesting_amount = 1000;
+        let address = Addr::unchecked("user1");
+        let info = mock_info(address.as_str(), &coins(vesting_amount, DENOM));
+
+        // * FAIL for cliff_time < start_time
+        let msg = ExecuteMsg::RegisterVestingAccount {
+            master_address: "master".to_string(),
+            address: address.to_string(),
+            vesting_schedule: VestingSchedule::LinearVestingWithCliff {
+                start_time: 1200,
+                end_time: 2200,
+                cliff_time: 1100,
+                vesting_amount: Uint128::from(vesting_amount),
+            },
+            revocable: true,
+            transfer_policy: None,
+        };
+        let result = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        match result {
+            ContractError::Schedule(ScheduleError::InvalidCliffTime { .. }) => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL for cliff_time > end_time
+        let msg = ExecuteMsg::RegisterVestingAccount {
+            master_address: "master".to_string(),
+            address: address.to_string(),
+            vesting_schedule: VestingSchedule::LinearVestingWithCliff {
+                start_time: 1200,
+                end_time: 2200,
+                cliff_time: 2300,
+                vesting_amount: Uint128::from(vesting_amount),
+            },
+            revocable: true,
+            transfer_policy: None,
+        };
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match result {
+            ContractError::Schedule(ScheduleError::InvalidCliffTime { .. }) => {}
+            e => panic!("{:?}", e),
+        };
+    }
+
+    #[test]
+    fn claim_linear_vesting_with_cliff() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(1000);
+
+        let address = Addr::unchecked("user1");
+        let info = mock_info(address.as_str(), &coins(1000, DENOM));
+        let msg = ExecuteMsg::RegisterVestingAccount {
+            master_address: "master".to_string(),
+            address: address.to_string(),
+            vesting_schedule: VestingSchedule::LinearVestingWithCliff {
+                start_time: 1000,
+                end_time: 2000,
+                cliff_time: 1500,
+                vesting_amount: Uint128::from(1000u128),
+            },
+            revocable: true,
+            transfer_policy: None,
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let claim_msg = ExecuteMsg::Claim {
+            denoms: vec![Denom::Native(DENOM.to_string())],
+            grant_id: None,
+            recipient: None,
+        };
+
+        // Nothing is claimable before the cliff
+        env.block.time = Timestamp::from_seconds(1499);
+        let info = mock_info(address.as_str(), &[]);
+        let result = execute(deps.as_mut(), env.clone(), info.clone(), claim_msg.clone()).unwrap();
+        assert_eq!(result.messages.len(), 0);
+
+        // Accrual since start_time unlocks at the cliff
+        env.block.time = Timestamp::from_seconds(1500);
+        let result = execute(deps.as_mut(), env, info, claim_msg).unwrap();
+        assert_eq!(
+            result.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: address.to_string(),
+                amount: coins(500, DENOM),
+            })
+        );
+    }
+
+    #[test]
+    fn top_up_vesting_account() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(100);
+
+        let address = Addr::unchecked("user1");
+        let msg = ExecuteMsg::RegisterVestingAccount {
+            master_address: "master".to_string(),
+            address: address.to_string(),
+            vesting_schedule: VestingSchedule::PeriodicVesting {
+                start_time: 1000,
+                end_time: 5000,
+                vesting_interval: 1000,
+                amount: Uint128::new(100),
+            },
+            revocable: true,
+            transfer_policy: None,
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("master", &coins(400, DENOM)),
+            msg,
+        )
+        .unwrap();
+
+        env.block.time = Timestamp::from_seconds(2500);
+        let claim = ExecuteMsg::Claim {
+            denoms: vec![Denom::Native(DENOM.to_string())],
+            grant_id: None,
+            recipient: None,
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(address.as_str(), &[]),
+            claim,
+        )
+        .unwrap();
+
+        let top_up = ExecuteMsg::TopUpVestingAccount {
+            address: address.to_string(),
+            grant_id: 1,
+            schedule_extension: ScheduleExtension::AppendPeriods { periods: 2 },
+        };
+
+        // * FAIL: only the master can top up
+        let info = mock_info("treasury", &coins(200, DENOM));
+        let result = execute(deps.as_mut(), env.clone(), info, top_up.clone()).unwrap_err();
+        match result {
+            ContractError::Unauthorized {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: the grant must exist in the deposited denom
+        let info = mock_info("master", &coins(200, "ATOM"));
+        let result = execute(deps.as_mut(), env.clone(), info, top_up.clone()).unwrap_err();
+        match result {
+            ContractError::VestingAccountNotFound { denom, grant_id }
+                if denom == "native-ATOM" && grant_id == Some(1) => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: deposit must match the appended periods
+        let info = mock_info("master", &coins(150, DENOM));
+        let result = execute(deps.as_mut(), env.clone(), info, top_up.clone()).unwrap_err();
+        match result {
+            ContractError::Schedule(ScheduleError::DepositMismatch { .. }) => {}
+            e => panic!("{:?}", e),
+        };
+
+        let info = mock_info("master", &coins(200, DENOM));
+        execute(deps.as_mut(), env.clone(), info, top_up).unwrap();
+
+        // Same grant, claim history kept
+        let account = vesting_accounts()
+            .load(deps.as_ref().storage, (address.as_str(), DENOM_KEY, 1))
+            .unwrap();
+        assert_eq!(account.vesting_amount, Uint128::new(600));
+        assert_eq!(account.claimed_amount, Uint128::new(100));
+        assert_eq!(
+            account.vesting_schedule,
+            VestingSchedule::PeriodicVesting {
+                start_time: 1000,
+                end_time: 7000,
+                vesting_interval: 1000,
+                amount: Uint128::new(100),
+            }
+        );
+        let stats = DENOM_STATS.load(deps.as_ref().storage, DENOM_KEY).unwrap();
+        assert_eq!(stats.deposited, Uint128::new(600));
+        assert_eq!(stats.outstanding, Uint128::new(500));
+        assert_eq!(stats.active_grants, 1);
+
+        // cw20 grants are topped up through the Receive hook
+        accept_cw20_token(deps.as_mut(), "token");
+        let hook = Cw20HookMsg::RegisterVestingAccount {
+            master_address: "master".to_string(),
+            address: address.to_string(),
+            vesting_schedule: VestingSchedule::LinearVesting {
+                start_time: 3000,
+                end_time: 4000,
+                vesting_amount: Uint128::new(1000),
+            },
+            revocable: true,
+            transfer_policy: None,
+        };
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "master".to_string(),
+            amount: Uint128::new(1000),
+            msg: to_json_binary(&hook).unwrap(),
+        });
+        execute(deps.as_mut(), env.clone(), mock_info("token", &[]), msg).unwrap();
+
+        let hook = Cw20HookMsg::TopUpVestingAccount {
+            address: address.to_string(),
+            grant_id: 2,
+            schedule_extension: ScheduleExtension::Scale {},
+        };
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "master".to_string(),
+            amount: Uint128::new(500),
+            msg: to_json_binary(&hook).unwrap(),
+        });
+        execute(deps.as_mut(), env, mock_info("token", &[]), msg).unwrap();
+        let account = vesting_accounts()
+            .load(deps.as_ref().storage, (address.as_str(), "cw20-token", 2))
+            .unwrap();
+        assert_eq!(account.vesting_amount, Uint128::new(1500));
+        assert_eq!(
+            account.vesting_schedule.total_amount().unwrap(),
+            Uint128::new(1500)
+        );
+    }
+
+    #[test]
+    fn transfer_vesting_account() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(100);
+        for transfer_policy in [
+            None,
+            Some(TransferPolicy::Transferable),
+            Some(TransferPolicy::MasterApproval),
+        ] {
+            let msg = ExecuteMsg::RegisterVestingAccount {
+                master_address: "master".to_string(),
+                address: "user1".to_string(),
+                vesting_schedule: VestingSchedule::LinearVesting {
+                    start_time: 1000,
+                    end_time: 2000,
+                    vesting_amount: Uint128::new(1000),
+                },
+                revocable: true,
+                transfer_policy,
+            };
+            let info = mock_info("master", &coins(1000, DENOM));
+            execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        }
+
+        env.block.time = Timestamp::from_seconds(1500);
+        let claim = ExecuteMsg::Claim {
+            denoms: vec![Denom::Native(DENOM.to_string())],
+            grant_id: Some(2),
+            recipient: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("user1", &[]), claim).unwrap();
+
+        let transfer =
+            |grant_id: Option<u64>, new_address: &str| ExecuteMsg::TransferVestingAccount {
+                denom: Denom::Native(DENOM.to_string()),
+                grant_id,
+                new_address: new_address.to_string(),
+            };
+
+        // * FAIL: grants are non-transferable by default
+        let info = mock_info("user1", &[]);
+        let result =
+            execute(deps.as_mut(), env.clone(), info, transfer(Some(1), "user2")).unwrap_err();
+        match result {
+            ContractError::NotTransferable { denom, grant_id }
+                if denom == DENOM_KEY && grant_id == Some(1) => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: cannot transfer to the current address
+        let info = mock_info("user1", &[]);
+        let result =
+            execute(deps.as_mut(), env.clone(), info, transfer(None, "user1")).unwrap_err();
+        match result {
+            ContractError::InvalidTransferRecipient {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        let info = mock_info("user1", &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, transfer(None, "user2")).unwrap();
+        assert_eq!(
+            result.attributes[4..],
+            vec![
+                Attribute::new("transferred_grant_id", "2"),
+                Attribute::new("pending_grant_id", "3"),
+            ]
+        );
+
+        // the transferable grant moved with its claim history
+        let account = vesting_accounts()
+            .load(deps.as_ref().storage, ("user2", DENOM_KEY, 2))
+            .unwrap();
+        assert_eq!(account.address, "user2");
+        assert_eq!(account.claimed_amount, Uint128::new(500));
+        assert!(vesting_accounts()
+            .may_load(deps.as_ref().storage, ("user1", DENOM_KEY, 2))
+            .unwrap()
+            .is_none());
+
+        // the other grants stay until the master approves
+        let account = vesting_accounts()
+            .load(deps.as_ref().storage, ("user1", DENOM_KEY, 3))
+            .unwrap();
+        assert_eq!(account.pending_transfer, Some("user2".to_string()));
+
+        let approve = |grant_id: u64, new_address: &str| ExecuteMsg::ApproveVestingTransfer {
+            address: "user1".to_string(),
+            denom: Denom::Native(DENOM.to_string()),
+            grant_id,
+            new_address: new_address.to_string(),
+        };
+
+        // * FAIL: only the master can approve
+        let info = mock_info("user1", &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, approve(3, "user2")).unwrap_err();
+        match result {
+            ContractError::Unauthorized {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: nothing to approve
+        let info = mock_info("master", &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, approve(1, "user2")).unwrap_err();
+        match result {
+            ContractError::NoPendingTransfer { grant_id: 1 } => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: the pending transfer was replaced before the approval landed
+        let info = mock_info("user1", &[]);
+        execute(deps.as_mut(), env.clone(), info, transfer(Some(3), "user3")).unwrap();
+        let info = mock_info("master", &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, approve(3, "user2")).unwrap_err();
+        match result {
+            ContractError::PendingTransferMismatch {
+                grant_id: 3,
+                pending_transfer,
+            } if pending_transfer == "user3" => {}
+            e => panic!("{:?}", e),
+        };
+
+        let info = mock_info("user1", &[]);
+        execute(deps.as_mut(), env.clone(), info, transfer(Some(3), "user2")).unwrap();
+        let info = mock_info("master", &[]);
+        execute(deps.as_mut(), env.clone(), info, approve(3, "user2")).unwrap();
+
+        let res: VestingAccountResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::VestingAccount {
+                    address: "user2".to_string(),
+                    start_after: None,
+                    limit: None,
+                    at_time: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let grants: Vec<(u64, Option<String>)> = res
+            .vestings
+            .into_iter()
+            .map(|vesting| (vesting.grant_id, vesting.pending_transfer))
+            .collect();
+        assert_eq!(grants, vec![(2, None), (3, None)]);
+
+        // the master index follows the new keys
+        let res: VestingAccountsByMasterResponse = from_json(
+            query(
+                deps.as_ref(),
+                env,
+                QueryMsg::VestingAccountsByMaster {
+                    master: "master".to_string(),
+                    start_after: None,
+                    limit: None,
+                    at_time: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let owners: Vec<(String, u64)> = res
+            .vestings
+            .into_iter()
+            .map(|vesting| (vesting.address, vesting.grant_id))
+            .collect();
+        assert_eq!(
+            owners,
+            vec![
+                ("user1".to_string(), 1),
+                ("user2".to_string(), 2),
+                ("user2".to_string(), 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn cw721_grants() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(100);
+        for (address, transfer_policy) in [
+            ("user1", Some(TransferPolicy::Transferable)),
+            ("user1", None),
+            ("user2", Some(TransferPolicy::Transferable)),
+        ] {
+            let msg = ExecuteMsg::RegisterVestingAccount {
+                master_address: "master".to_string(),
+                address: address.to_string(),
+                vesting_schedule: VestingSchedule::LinearVesting {
+                    start_time: 1000,
+                    end_time: 2000,
+                    vesting_amount: Uint128::new(1000),
+                },
+                revocable: true,
+                transfer_policy,
+            };
+            let info = mock_info("master", &coins(1000, DENOM));
+            execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        }
+
+        let query_tokens = |deps: Deps, owner: &str, start_after: Option<&str>| {
+            let res: TokensResponse = from_json(
+                query(
+                    deps,
+                    mock_env(),
+                    QueryMsg::Tokens {
+                        owner: owner.to_string(),
+                        start_after: start_after.map(|token_id| token_id.to_string()),
+                        limit: None,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            res.tokens
+        };
+        let query_owner = |deps: Deps, token_id: &str| {
+            let res: OwnerOfResponse = from_json(
+                query(
+                    deps,
+                    mock_env(),
+                    QueryMsg::OwnerOf {
+                        token_id: token_id.to_string(),
+                        include_expired: None,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            res
+        };
+
+        let res: NumTokensResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::NumTokens {}).unwrap()).unwrap();
+        assert_eq!(res.count, 3);
+        let res: TokensResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::AllTokens {
+                    start_after: Some("1".to_string()),
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.tokens, vec!["2", "3"]);
+        assert_eq!(query_tokens(deps.as_ref(), "user1", None), vec!["1", "2"]);
+        assert_eq!(query_tokens(deps.as_ref(), "user1", Some("1")), vec!["2"]);
+        assert_eq!(query_owner(deps.as_ref(), "1").owner, "user1");
+
+        // * FAIL: token ids are grant ids
+        let result = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::NftInfo {
+                token_id: "grant".to_string(),
+            },
+        );
+        assert!(result.is_err());
+
+        let transfer = |token_id: &str| ExecuteMsg::TransferNft {
+            recipient: "user3".to_string(),
+            token_id: token_id.to_string(),
+        };
+
+        // * FAIL: only the holder or an approved spender can transfer
+        let info = mock_info("desk", &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, transfer("1")).unwrap_err();
+        match result {
+            ContractError::Unauthorized {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: unknown token ids
+        let info = mock_info("user1", &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, transfer("grant")).unwrap_err();
+        match result {
+            ContractError::InvalidTokenId { token_id } if token_id == "grant" => {}
+            e => panic!("{:?}", e),
+        };
+        let info = mock_info("user1", &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, transfer("9")).unwrap_err();
+        match result {
+            ContractError::TokenNotFound { token_id } if token_id == "9" => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: approvals must not be expired already
+        let approve = |expires: Expiration| ExecuteMsg::Approve {
+            spender: "desk".to_string(),
+            token_id: "1".to_string(),
+            expires: Some(expires),
+        };
+        let info = mock_info("user1", &[]);
+        let msg = approve(Expiration::AtHeight(env.block.height));
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match result {
+            ContractError::Expired {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        let info = mock_info("user1", &[]);
+        let msg = approve(Expiration::AtHeight(env.block.height + 10));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(
+            query_owner(deps.as_ref(), "1").approvals,
+            vec![Approval {
+                spender: "desk".to_string(),
+                expires: Expiration::AtHeight(env.block.height + 10),
+            }]
+        );
+        let res: ApprovalResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Approval {
+                    token_id: "1".to_string(),
+                    spender: "desk".to_string(),
+                    include_expired: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.approval.spender, "desk");
+        let res: ApprovalsResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Approvals {
+                    token_id: "1".to_string(),
+                    include_expired: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.approvals.len(), 1);
+
+        // expired approvals are only listed on request
+        let mut later = env.clone();
+        later.block.height += 10;
+        let approvals = |include_expired| {
+            let res: ApprovalsResponse = from_json(
+                query(
+                    deps.as_ref(),
+                    later.clone(),
+                    QueryMsg::Approvals {
+                        token_id: "1".to_string(),
+                        include_expired: Some(include_expired),
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            res.approvals.len()
+        };
+        assert_eq!((approvals(false), approvals(true)), (0, 1));
+        query(
+            deps.as_ref(),
+            later,
+            QueryMsg::Approval {
+                token_id: "1".to_string(),
+                spender: "desk".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap_err();
+
+        // grants are minted by registration
+        let res: MinterResponse =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Minter {}).unwrap()).unwrap();
+        assert_eq!(res.minter, None);
+
+        let info = mock_info("desk", &[]);
+        execute(deps.as_mut(), env.clone(), info, transfer("1")).unwrap();
+
+        // the new holder gets the grant, without the approvals of the old one
+        let res = query_owner(deps.as_ref(), "1");
+        assert_eq!(res.owner, "user3");
+        assert!(res.approvals.is_empty());
+        assert_eq!(query_tokens(deps.as_ref(), "user1", None), vec!["2"]);
+        assert!(vesting_accounts()
+            .may_load(deps.as_ref().storage, ("user3", DENOM_KEY, 1))
+            .unwrap()
+            .is_some());
+
+        // * FAIL: non-transferable grants cannot be traded
+        let info = mock_info("user1", &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, transfer("2")).unwrap_err();
+        match result {
+            ContractError::NotTransferable {
+                grant_id: Some(2), ..
+            } => {}
+            e => panic!("{:?}", e),
+        };
+
+        // approvals can be revoked
+        let info = mock_info("user1", &[]);
+        let msg = ExecuteMsg::Approve {
+            spender: "desk".to_string(),
+            token_id: "2".to_string(),
+            expires: None,
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let info = mock_info("user1", &[]);
+        let msg = ExecuteMsg::Revoke {
+            spender: "desk".to_string(),
+            token_id: "2".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert!(query_owner(deps.as_ref(), "2").approvals.is_empty());
+
+        // send notifies the receiving contract
+        let info = mock_info("user2", &[]);
+        let msg = ExecuteMsg::SendNft {
+            contract: "escrow".to_string(),
+            token_id: "3".to_string(),
+            msg: Binary::from(b"{}"),
+        };
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(
+            result.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "escrow".to_string(),
+                msg: Binary::from(
+                    br#"{"receive_nft":{"sender":"user2","token_id":"3","msg":"e30="}}"#
+                ),
+                funds: vec![],
+            })
+        );
+
+        let res: NftInfoResponse = from_json(
+            query(
+                deps.as_ref(),
+                env,
+                QueryMsg::NftInfo {
+                    token_id: "3".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.token_uri, None);
+        assert_eq!(res.extension.address, "escrow");
+        assert_eq!(res.extension.vesting_amount, Uint128::new(1000));
+    }
+
+    #[test]
+    fn cw721_operators_and_burn() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(100);
+        for _ in 0..3 {
+            let msg = ExecuteMsg::RegisterVestingAccount {
+                master_address: "master".to_string(),
+                address: "user1".to_string(),
+                vesting_schedule: VestingSchedule::LinearVesting {
+                    start_time: 1000,
+                    end_time: 2000,
+                    vesting_amount: Uint128::new(1000),
+                },
+                revocable: true,
+                transfer_policy: Some(TransferPolicy::Transferable),
+            };
+            let info = mock_info("master", &coins(1000, DENOM));
+            execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        }
+
+        let transfer = |token_id: &str| ExecuteMsg::TransferNft {
+            recipient: "user3".to_string(),
+            token_id: token_id.to_string(),
+        };
+        let query_operators = |deps: Deps, env: Env, include_expired| {
+            let res: OperatorsResponse = from_json(
+                query(
+                    deps,
+                    env,
+                    QueryMsg::AllOperators {
+                        owner: "user1".to_string(),
+                        include_expired: Some(include_expired),
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            res.operators
+        };
+
+        // * FAIL: operator approvals must not be expired already
+        let info = mock_info("user1", &[]);
+        let msg = ExecuteMsg::ApproveAll {
+            operator: "desk".to_string(),
+            expires: Some(Expiration::AtHeight(env.block.height)),
+        };
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match result {
+            ContractError::Expired {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: funds not allowed
+        let info = mock_info("user1", &coins(10, DENOM));
+        let msg = ExecuteMsg::ApproveAll {
+            operator: "desk".to_string(),
+            expires: None,
+        };
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match result {
+            ContractError::FundsNotAllowed {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        let expires = Expiration::AtHeight(env.block.height + 10);
+        for operator in ["desk", "broker"] {
+            let info = mock_info("user1", &[]);
+            let msg = ExecuteMsg::ApproveAll {
+                operator: operator.to_string(),
+                expires: Some(expires),
+            };
+            execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        }
+        let res: OperatorResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Operator {
+                    owner: "user1".to_string(),
+                    operator: "desk".to_string(),
+                    include_expired: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.approval,
+            Approval {
+                spender: "desk".to_string(),
+                expires,
+            }
+        );
+        let operators: Vec<String> = query_operators(deps.as_ref(), env.clone(), false)
+            .into_iter()
+            .map(|approval| approval.spender)
+            .collect();
+        assert_eq!(operators, vec!["broker", "desk"]);
+        let res: OperatorsResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::AllOperators {
+                    owner: "user1".to_string(),
+                    include_expired: None,
+                    start_after: Some("broker".to_string()),
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.operators.len(), 1);
+        assert_eq!(res.operators[0].spender, "desk");
+
+        // operators move grants and approve spenders on behalf of the holder
+        let info = mock_info("desk", &[]);
+        execute(deps.as_mut(), env.clone(), info, transfer("1")).unwrap();
+        let info = mock_info("desk", &[]);
+        let msg = ExecuteMsg::Approve {
+            spender: "escrow".to_string(),
+            token_id: "2".to_string(),
+            expires: None,
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // * FAIL: operators of the old holder lose the grant
+        let info = mock_info("desk", &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, transfer("1")).unwrap_err();
+        match result {
+            ContractError::Unauthorized {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: expired operators can no longer act
+        let mut later = env.clone();
+        later.block.height += 10;
+        let info = mock_info("desk", &[]);
+        let result = execute(deps.as_mut(), later.clone(), info, transfer("3")).unwrap_err();
+        match result {
+            ContractError::Unauthorized {} => {}
+            e => panic!("{:?}", e),
+        };
+        query(
+            deps.as_ref(),
+            later.clone(),
+            QueryMsg::Operator {
+                owner: "user1".to_string(),
+                operator: "desk".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap_err();
+        assert!(query_operators(deps.as_ref(), later.clone(), false).is_empty());
+        assert_eq!(query_operators(deps.as_ref(), later.clone(), true).len(), 2);
+
+        let info = mock_info("user1", &[]);
+        let msg = ExecuteMsg::RevokeAll {
+            operator: "desk".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let operators = query_operators(deps.as_ref(), env.clone(), true);
+        assert_eq!(operators.len(), 1);
+        assert_eq!(operators[0].spender, "broker");
+
+        // * FAIL: only the holder, a spender or an operator can burn
+        let burn = |token_id: &str| ExecuteMsg::Burn {
+            token_id: token_id.to_string(),
+        };
+        let info = mock_info("desk", &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, burn("2")).unwrap_err();
+        match result {
+            ContractError::Unauthorized {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        // a spender burns the grant: the vested part goes to the holder, the rest to the master
+        env.block.time = Timestamp::from_seconds(1500);
+        let info = mock_info("user1", &[]);
+        let msg = ExecuteMsg::SetClaimRecipient {
+            recipient: Some("vault".to_string()),
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let info = mock_info("escrow", &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, burn("2")).unwrap();
+        let messages: Vec<CosmosMsg> = result.messages.into_iter().map(|msg| msg.msg).collect();
+        assert_eq!(
+            messages,
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "vault".to_string(),
+                    amount: coins(500, DENOM),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "master".to_string(),
+                    amount: coins(500, DENOM),
+                }),
+            ]
+        );
+        assert_eq!(result.attributes[0], Attribute::new("action", "burn"));
+        query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::OwnerOf {
+                token_id: "2".to_string(),
+                include_expired: None,
+            },
+        )
+        .unwrap_err();
+        let stats = DENOM_STATS.load(deps.as_ref().storage, DENOM_KEY).unwrap();
+        assert_eq!(stats.active_grants, 2);
+        assert_eq!(stats.claimed, Uint128::new(500));
+        assert_eq!(stats.returned, Uint128::new(500));
+        assert_eq!(stats.outstanding, Uint128::new(2000));
+    }
+
+    #[test]
+    fn split_vesting_account() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(100);
+        for (transfer_policy, revocable) in
+            [(Some(TransferPolicy::Transferable), false), (None, true)]
+        {
+            let msg = ExecuteMsg::RegisterVestingAccount {
+                master_address: "master".to_string(),
+                address: "user1".to_string(),
+                vesting_schedule: VestingSchedule::LinearVesting {
+                    start_time: 1000,
+                    end_time: 2000,
+                    vesting_amount: Uint128::new(1000),
+                },
+                revocable,
+                transfer_policy,
+            };
+            let info = mock_info("master", &coins(1000, DENOM));
+            execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        }
+
+        env.block.time = Timestamp::from_seconds(1500);
+        let claim = ExecuteMsg::Claim {
+            denoms: vec![Denom::Native(DENOM.to_string())],
+            grant_id: Some(1),
+            recipient: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("user1", &[]), claim).unwrap();
+
+        let split = |grant_id: u64, amount_or_ratio: SplitAmount| ExecuteMsg::SplitVestingAccount {
+            denom: Denom::Native(DENOM.to_string()),
+            grant_id,
+            amount_or_ratio,
+            new_beneficiary: "user2".to_string(),
+        };
+        let quarter = SplitAmount::Ratio(Decimal::percent(25));
+
+        // * FAIL: only the beneficiary or the master can split
+        let info = mock_info("user2", &[]);
+        let result =
+            execute(deps.as_mut(), env.clone(), info, split(1, quarter.clone())).unwrap_err();
+        match result {
+            ContractError::Unauthorized {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: the master cannot split off an irrevocable grant
+        let info = mock_info("master", &[]);
+        let result =
+            execute(deps.as_mut(), env.clone(), info, split(1, quarter.clone())).unwrap_err();
+        match result {
+            ContractError::NotRevocable { .. } => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: the beneficiary cannot split off a non-transferable grant
+        let info = mock_info("user1", &[]);
+        let result =
+            execute(deps.as_mut(), env.clone(), info, split(2, quarter.clone())).unwrap_err();
+        match result {
+            ContractError::NotTransferable { .. } => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: the split must leave something on both grants
+        let info = mock_info("user1", &[]);
+        let msg = split(1, SplitAmount::Amount(Uint128::new(1000)));
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match result {
+            ContractError::Schedule(ScheduleError::InvalidSplit { .. }) => {}
+            e => panic!("{:?}", e),
+        };
+
+        let info = mock_info("user1", &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, split(1, quarter.clone())).unwrap();
+        assert_eq!(result.attributes[4], Attribute::new("new_grant_id", "3"));
+
+        // schedules and claimed amounts are split in proportion
+        let account = vesting_accounts()
+            .load(deps.as_ref().storage, ("user1", DENOM_KEY, 1))
+            .unwrap();
+        assert_eq!(account.vesting_amount, Uint128::new(750));
+        assert_eq!(account.claimed_amount, Uint128::new(375));
+        let account = vesting_accounts()
+            .load(deps.as_ref().storage, ("user2", DENOM_KEY, 3))
+            .unwrap();
+        assert_eq!(account.vesting_amount, Uint128::new(250));
+        assert_eq!(account.claimed_amount, Uint128::new(125));
+        assert_eq!(account.master_address, "master");
+        assert_eq!(account.transfer_policy, TransferPolicy::Transferable);
+        let stats = DENOM_STATS.load(deps.as_ref().storage, DENOM_KEY).unwrap();
+        assert_eq!(stats.active_grants, 3);
+        assert_eq!(stats.outstanding, Uint128::new(1500));
+
+        // the master may split revocable grants, paying out what has vested first
+        let info = mock_info("master", &[]);
+        let msg = split(2, SplitAmount::Amount(Uint128::new(400)));
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(
+            result.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "user1".to_string(),
+                amount: coins(500, DENOM),
+            })
+        );
+        let account = vesting_accounts()
+            .load(deps.as_ref().storage, ("user1", DENOM_KEY, 2))
+            .unwrap();
+        assert_eq!(account.vesting_amount, Uint128::new(600));
+        assert_eq!(account.claimed_amount, Uint128::new(300));
+        let account = vesting_accounts()
+            .load(deps.as_ref().storage, ("user2", DENOM_KEY, 4))
+            .unwrap();
+        assert_eq!(account.vesting_amount, Uint128::new(400));
+        assert_eq!(account.claimed_amount, Uint128::new(200));
+        let stats = DENOM_STATS.load(deps.as_ref().storage, DENOM_KEY).unwrap();
+        assert_eq!(stats.active_grants, 4);
+        assert_eq!(stats.outstanding, Uint128::new(1000));
+
+        // the new beneficiary only shares in what vests after the split
+        env.block.time = Timestamp::from_seconds(1600);
+        for address in ["user1", "user2"] {
+            let res: VestingAccountResponse = from_json(
+                query(
+                    deps.as_ref(),
+                    env.clone(),
+                    QueryMsg::VestingAccount {
+                        address: address.to_string(),
+                        start_after: None,
+                        limit: None,
+                        at_time: None,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            let claimable: Vec<(u64, Uint128)> = res
+                .vestings
+                .into_iter()
+                .map(|vesting| (vesting.grant_id, vesting.claimable_amount))
+                .collect();
+            if address == "user1" {
+                assert_eq!(
+                    claimable,
+                    vec![(1, Uint128::new(75)), (2, Uint128::new(60))]
+                );
+            } else {
+                assert_eq!(
+                    claimable,
+                    vec![(3, Uint128::new(25)), (4, Uint128::new(40))]
+                );
+            }
+        }
+
+        // * FAIL: the master cannot split a grant which has fully vested
+        env.block.time = Timestamp::from_seconds(2000);
+        let info = mock_info("master", &[]);
+        let result = execute(deps.as_mut(), env, info, split(2, quarter)).unwrap_err();
+        match result {
+            ContractError::FullyVested { grant_id: 2 } => {}
+            e => panic!("{:?}", e),
+        };
+    }
+
+    #[test]
+    fn claim_operators() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(100);
+        let msg = ExecuteMsg::RegisterVestingAccount {
+            master_address: "master".to_string(),
+            address: "user1".to_string(),
+            vesting_schedule: VestingSchedule::LinearVesting {
+                start_time: 1000,
+                end_time: 2000,
+                vesting_amount: Uint128::new(1000),
+            },
+            revocable: true,
+            transfer_policy: None,
+        };
+        let info = mock_info("master", &coins(1000, DENOM));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        env.block.time = Timestamp::from_seconds(1500);
+        let claim_for = ExecuteMsg::ClaimFor {
+            owner: "user1".to_string(),
+            denoms: vec![Denom::Native(DENOM.to_string())],
+        };
+
+        // * FAIL: the operator must be approved
+        let info = mock_info("bot", &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, claim_for.clone()).unwrap_err();
+        match result {
+            ContractError::Unauthorized {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        let approve = |expires: Expiration| ExecuteMsg::ApproveClaimOperator {
+            operator: "bot".to_string(),
+            denom: Denom::Native(DENOM.to_string()),
+            expires: Some(expires),
+        };
+
+        // * FAIL: approvals must not be expired already
+        let info = mock_info("user1", &[]);
+        let msg = approve(Expiration::AtHeight(env.block.height));
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match result {
+            ContractError::Expired {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        let expires = Expiration::AtHeight(env.block.height + 10);
+        let info = mock_info("user1", &[]);
+        execute(deps.as_mut(), env.clone(), info, approve(expires)).unwrap();
+        let info = mock_info("user1", &[]);
+        let msg = ExecuteMsg::SetClaimRecipient {
+            recipient: Some("custody".to_string()),
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let res: ClaimOperatorsResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::ClaimOperators {
+                    owner: "user1".to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.operators,
+            vec![ClaimOperator {
+                operator: "bot".to_string(),
+                denom: Denom::Native(DENOM.to_string()),
+                expires,
+            }]
+        );
+
+        // the operator claims, the tokens go to the claim recipient
+        let info = mock_info("bot", &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, claim_for.clone()).unwrap();
+        assert_eq!(
+            result.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "custody".to_string(),
+                amount: coins(500, DENOM),
+            })
+        );
+        assert_eq!(result.attributes[2], Attribute::new("operator", "bot"));
+
+        // the beneficiary's own claims default to the claim recipient as well
+        env.block.time = Timestamp::from_seconds(1600);
+        let info = mock_info("user1", &[]);
+        let msg = ExecuteMsg::Claim {
+            denoms: vec![Denom::Native(DENOM.to_string())],
+            grant_id: None,
+            recipient: None,
+        };
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(
+            result.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "custody".to_string(),
+                amount: coins(100, DENOM),
+            })
+        );
+
+        // * FAIL: the approval has expired
+        env.block.height += 10;
+        let info = mock_info("bot", &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, claim_for).unwrap_err();
+        match result {
+            ContractError::Unauthorized {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        let info = mock_info("user1", &[]);
+        let msg = ExecuteMsg::RevokeClaimOperator {
+            operator: "bot".to_string(),
+            denom: Denom::Native(DENOM.to_string()),
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let info = mock_info("user1", &[]);
+        let msg = ExecuteMsg::SetClaimRecipient { recipient: None };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let res: ClaimOperatorsResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::ClaimOperators {
+                    owner: "user1".to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(res.operators.is_empty());
+        let res: ClaimRecipientResponse = from_json(
+            query(
+                deps.as_ref(),
+                env,
+                QueryMsg::ClaimRecipient {
+                    owner: "user1".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.recipient, "user1");
+    }
+
+    #[test]
+    fn distribute_vested() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        let update_bounty = |bounty: Decimal| ExecuteMsg::UpdateConfig {
+            owner: None,
+            app_id: None,
+            vesting_contract: None,
+            open_registration: None,
+            distribution_bounty: Some(bounty),
+        };
+
+        // * FAIL: the bounty is capped
+        let info = mock_info("owner", &[]);
+        let msg = update_bounty(Decimal::percent(10));
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match result {
+            ContractError::InvalidDistributionBounty { .. } => {}
+            e => panic!("{:?}", e),
+        };
+
+        let info = mock_info("owner", &[]);
+        let msg = update_bounty(Decimal::percent(1));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        env.block.time = Timestamp::from_seconds(100);
+        let vesting_schedule = VestingSchedule::LinearVesting {
+            start_time: 1000,
+            end_time: 2000,
+            vesting_amount: Uint128::new(1000),
+        };
+        for address in ["user1", "user2"] {
+            let msg = ExecuteMsg::RegisterVestingAccount {
+                master_address: "master".to_string(),
+                address: address.to_string(),
+                vesting_schedule: vesting_schedule.clone(),
+                revocable: true,
+                transfer_policy: None,
+            };
+            let info = mock_info("master", &coins(1000, DENOM));
+            execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        }
+        accept_cw20_token(deps.as_mut(), "token");
+        let hook = Cw20HookMsg::RegisterVestingAccount {
+            master_address: "master".to_string(),
+            address: "user1".to_string(),
+            vesting_schedule,
+            revocable: true,
+            transfer_policy: None,
+        };
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "master".to_string(),
+            amount: Uint128::new(1000),
+            msg: to_json_binary(&hook).unwrap(),
+        });
+        execute(deps.as_mut(), env.clone(), mock_info("token", &[]), msg).unwrap();
+
+        // * FAIL: the accepted bounty is bounded like the configured one
+        let info = mock_info("user1", &[]);
+        let msg = ExecuteMsg::SetAutoDistribution {
+            enabled: true,
+            max_bounty: Some(Decimal::percent(10)),
+        };
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match result {
+            ContractError::InvalidDistributionBounty { .. } => {}
+            e => panic!("{:?}", e),
+        };
+
+        // the configured bounty is accepted by default
+        let info = mock_info("user1", &[]);
+        let msg = ExecuteMsg::SetAutoDistribution {
+            enabled: true,
+            max_bounty: None,
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let res: AutoDistributionResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::AutoDistribution {
+                    owner: "user1".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(res.enabled);
+        assert_eq!(res.max_bounty, Decimal::percent(1));
+
+        // the grants of user1 come first in storage order
+        env.block.time = Timestamp::from_seconds(1500);
+
+        // an empty page distributes nothing and has no cursor
+        let msg = ExecuteMsg::DistributeVested {
+            start_after: None,
+            limit: Some(0),
+        };
+        let result = execute(deps.as_mut(), env.clone(), mock_info("keeper", &[]), msg).unwrap();
+        assert!(result.messages.is_empty());
+        assert_eq!(
+            result.attributes,
+            vec![
+                Attribute::new("action", "distribute_vested"),
+                Attribute::new("keeper", "keeper"),
+            ]
+        );
+
+        let msg = ExecuteMsg::DistributeVested {
+            start_after: None,
+            limit: Some(2),
+        };
+        let result = execute(deps.as_mut(), env.clone(), mock_info("keeper", &[]), msg).unwrap();
+        let cw20_transfer = |recipient: &str, amount: u128| {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "token".to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount: Uint128::new(amount),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        };
+        let bank_send = |to_address: &str, amount: u128| {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: to_address.to_string(),
+                amount: coins(amount, DENOM),
+            })
+        };
+        let messages: Vec<CosmosMsg> = result.messages.into_iter().map(|msg| msg.msg).collect();
+        assert_eq!(
+            messages,
+            vec![
+                cw20_transfer("user1", 495),
+                bank_send("user1", 495),
+                cw20_transfer("keeper", 5),
+                bank_send("keeper", 5),
+            ]
+        );
+        assert_eq!(
+            result.attributes.last().unwrap(),
+            &Attribute::new(
+                "next_start_after",
+                to_string(&("user1", Denom::Native(DENOM.to_string()), 1)).unwrap()
+            )
+        );
+        let stats = DENOM_STATS.load(deps.as_ref().storage, DENOM_KEY).unwrap();
+        assert_eq!(stats.claimed, Uint128::new(500));
+
+        // user2 did not opt in
+        let msg = ExecuteMsg::DistributeVested {
+            start_after: Some(("user1".to_string(), Denom::Native(DENOM.to_string()), 1)),
+            limit: Some(2),
+        };
+        let result = execute(deps.as_mut(), env.clone(), mock_info("keeper", &[]), msg).unwrap();
+        assert!(result.messages.is_empty());
+        assert_eq!(
+            result.attributes,
+            vec![
+                Attribute::new("action", "distribute_vested"),
+                Attribute::new("keeper", "keeper"),
+            ]
+        );
+
+        // raising the bounty does not apply to beneficiaries who accepted less
+        let info = mock_info("owner", &[]);
+        let msg = update_bounty(Decimal::percent(5));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        env.block.time = Timestamp::from_seconds(1600);
+        let msg = ExecuteMsg::DistributeVested {
+            start_after: None,
+            limit: Some(2),
+        };
+        let result = execute(deps.as_mut(), env, mock_info("keeper", &[]), msg).unwrap();
+        let messages: Vec<CosmosMsg> = result.messages.into_iter().map(|msg| msg.msg).collect();
+        assert_eq!(
+            messages,
+            vec![
+                cw20_transfer("user1", 99),
+                bank_send("user1", 99),
+                cw20_transfer("keeper", 1),
+                bank_send("keeper", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn register_vesting_accounts_batch() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(100);
+
+        let registration =
+            |address: &str, denom: &str, vesting_schedule| VestingAccountRegistration {
+                master_address: "master".to_string(),
+                address: address.to_string(),
+                denom: denom.to_string(),
+                vesting_schedule,
+                revocable: true,
+                transfer_policy: None,
+            };
+        let accounts = vec![
+            registration(
+                "user1",
+                DENOM,
+                VestingSchedule::LinearVesting {
+                    start_time: 1000,
+                    end_time: 2000,
+                    vesting_amount: Uint128::new(1000),
+                },
+            ),
+            registration(
+                "user2",
+                DENOM,
+                VestingSchedule::PeriodicVesting {
+                    start_time: 1000,
+                    end_time: 5000,
+                    vesting_interval: 1000,
+                    amount: Uint128::new(100),
+                },
+            ),
+            registration(
+                "user3",
+                "ATOM",
+                VestingSchedule::Custom {
+                    points: vec![(1000, Uint128::new(200)), (2000, Uint128::new(500))],
+                    interpolate: false,
+                },
+            ),
+        ];
+        let msg = ExecuteMsg::RegisterVestingAccounts {
+            accounts: accounts.clone(),
+        };
+
+        // * FAIL: empty batch
+        let info = mock_info("master", &coins(1400, DENOM));
+        let result = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::RegisterVestingAccounts { accounts: vec![] },
+        )
+        .unwrap_err();
+        match result {
+            ContractError::EmptyBatch {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: deposit short of the schedule totals
+        let info = mock_info("master", &[coin(1300, DENOM), coin(500, "ATOM")]);
+        let result = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        match result {
+            ContractError::BatchDepositMismatch {
+                denom,
+                deposit_amount,
+                vesting_amount,
+            } if denom == DENOM
+                && deposit_amount == Uint128::new(1300)
+                && vesting_amount == Uint128::new(1400) => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: coins not assigned to any grant
+        let info = mock_info(
+            "master",
+            &[coin(1400, DENOM), coin(500, "ATOM"), coin(10, "DNM")],
+        );
+        let result = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        match result {
+            ContractError::BatchDepositMismatch { denom, .. } if denom == "DNM" => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: an entry vesting nothing would be a grant without funds
+        let mut unfunded = accounts.clone();
+        unfunded.insert(
+            0,
+            registration(
+                "user4",
+                DENOM,
+                VestingSchedule::LinearVesting {
+                    start_time: 1000,
+                    end_time: 2000,
+                    vesting_amount: Uint128::zero(),
+                },
+            ),
+        );
+        let info = mock_info("master", &[coin(1400, DENOM), coin(500, "ATOM")]);
+        let unfunded = ExecuteMsg::RegisterVestingAccounts { accounts: unfunded };
+        let result = execute(deps.as_mut(), env.clone(), info, unfunded).unwrap_err();
+        match result {
+            ContractError::Schedule(ScheduleError::ZeroVestingAmount {}) => {}
+            e => panic!("{:?}", e),
+        };
+
+        let info = mock_info("master", &[coin(1400, DENOM), coin(500, "ATOM")]);
+        let result = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(result.attributes[1], ("count", "3"));
+        assert_eq!(
+            vesting_accounts()
+                .load(deps.as_ref().storage, ("user2", DENOM_KEY, 2))
+                .unwrap()
+                .vesting_amount,
+            Uint128::new(400)
+        );
+        assert!(vesting_accounts().has(deps.as_ref().storage, ("user3", "native-ATOM", 3)));
+        assert_eq!(
+            DENOM_STATS
+                .load(deps.as_ref().storage, DENOM_KEY)
+                .unwrap()
+                .deposited,
+            Uint128::new(1400)
+        );
+
+        // * FAIL: a single invalid schedule rejects the whole batch
+        let mut invalid = accounts;
+        invalid[1].vesting_schedule = VestingSchedule::LinearVesting {
+            start_time: 50,
+            end_time: 2000,
+            vesting_amount: Uint128::new(400),
+        };
+        let result = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::RegisterVestingAccounts { accounts: invalid },
+        )
+        .unwrap_err();
+        match result {
+            ContractError::Schedule(ScheduleError::StartTimeInPast { .. }) => {}
+            e => panic!("{:?}", e),
+        };
+    }
+
+    #[test]
+    fn validate_vesting_schedule_query() {
+        let mut env = mock_env();
+        let deps = mock_dependencies();
+
+        env.block.time = Timestamp::from_seconds(100);
+
+        let schedule = VestingSchedule::PeriodicVesting {
+            start_time: 1000,
             end_time: 5000,
             vesting_interval: 1000,
-            amount: Uint128::from(vesting_amount),
+            amount: Uint128::new(100),
+        };
+        let result = validate_vesting_schedule(&env, schedule.clone(), Uint128::new(400));
+        assert_eq!(
+            result,
+            ValidateVestingScheduleResponse::Valid {
+                schedule: schedule.clone(),
+                vesting_amount: Uint128::new(400),
+            }
+        );
+
+        // Same checks as registration, returned as a structured error
+        let msg = QueryMsg::ValidateVestingSchedule {
+            schedule,
+            deposit: Uint128::new(500),
+        };
+        let result: ValidateVestingScheduleResponse =
+            from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(
+            result,
+            ValidateVestingScheduleResponse::Invalid {
+                error: ScheduleError::DepositMismatch {
+                    deposit_amount: Uint128::new(500),
+                    vesting_amount: Uint128::new(400),
+                },
+            }
+        );
+
+        let schedule = VestingSchedule::LinearVestingWithCliff {
+            start_time: 50,
+            end_time: 200,
+            cliff_time: 100,
+            vesting_amount: Uint128::new(500),
+        };
+        let result = validate_vesting_schedule(&env, schedule, Uint128::new(500));
+        assert_eq!(
+            result,
+            ValidateVestingScheduleResponse::Invalid {
+                error: ScheduleError::StartTimeInPast {
+                    start_time: 50,
+                    block_time: 100,
+                },
+            }
+        );
+
+        let schedule = VestingSchedule::PeriodicVesting {
+            start_time: 1000,
+            end_time: 3000,
+            vesting_interval: 1000,
+            amount: Uint128::MAX,
+        };
+        let result = validate_vesting_schedule(&env, schedule, Uint128::MAX);
+        assert_eq!(
+            result,
+            ValidateVestingScheduleResponse::Invalid {
+                error: ScheduleError::VestingAmountOverflow {},
+            }
+        );
+
+        // Registration rejects empty deposits, and so does the query
+        let schedule = VestingSchedule::LinearVesting {
+            start_time: 1000,
+            end_time: 2000,
+            vesting_amount: Uint128::zero(),
+        };
+        let result = validate_vesting_schedule(&env, schedule, Uint128::zero());
+        assert_eq!(
+            result,
+            ValidateVestingScheduleResponse::Invalid {
+                error: ScheduleError::ZeroVestingAmount {},
+            }
+        );
+    }
+
+    #[test]
+    fn register_vesting_account_custom_invalid_request() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(1000);
+
+        let address = Addr::unchecked("user1");
+        let info = mock_info(address.as_str(), &coins(1000, DENOM));
+        let register = |points: Vec<(u64, Uint128)>| ExecuteMsg::RegisterVestingAccount {
+            master_address: "master".to_string(),
+            address: address.to_string(),
+            vesting_schedule: VestingSchedule::Custom {
+                points,
+                interpolate: false,
+            },
+            revocable: true,
+            transfer_policy: None,
+        };
+
+        // * FAIL for empty points
+        let result =
+            execute(deps.as_mut(), env.clone(), info.clone(), register(vec![])).unwrap_err();
+        match result {
+            ContractError::Schedule(ScheduleError::NoUnlockPoints {}) => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL for unordered timestamps
+        let points = vec![(2000, Uint128::new(100)), (1500, Uint128::new(1000))];
+        let result =
+            execute(deps.as_mut(), env.clone(), info.clone(), register(points)).unwrap_err();
+        match result {
+            ContractError::Schedule(ScheduleError::UnorderedUnlockPoints { index: 1 }) => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL for decreasing cumulative amounts
+        let points = vec![(1500, Uint128::new(500)), (2000, Uint128::new(400))];
+        let result =
+            execute(deps.as_mut(), env.clone(), info.clone(), register(points)).unwrap_err();
+        match result {
+            ContractError::Schedule(ScheduleError::DecreasingUnlockPoints { index: 1 }) => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL for final amount not matching the deposit
+        let points = vec![(1500, Uint128::new(100)), (2000, Uint128::new(900))];
+        let result = execute(deps.as_mut(), env, info, register(points)).unwrap_err();
+        match result {
+            ContractError::Schedule(ScheduleError::DepositMismatch { .. }) => {}
+            e => panic!("{:?}", e),
+        };
+    }
+
+    #[test]
+    fn register_vesting_account_custom_valid_request() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(1000);
+
+        let address = Addr::unchecked("user1");
+        let vesting_schedule = VestingSchedule::Custom {
+            points: vec![
+                (1000, Uint128::new(100)),
+                (2000, Uint128::new(400)),
+                (3000, Uint128::new(1000)),
+            ],
+            interpolate: false,
+        };
+        let info = mock_info(address.as_str(), &coins(1000, DENOM));
+        let msg = ExecuteMsg::RegisterVestingAccount {
+            master_address: "master".to_string(),
+            address: address.to_string(),
+            vesting_schedule: vesting_schedule.clone(),
+            revocable: true,
+            transfer_policy: None,
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let vesting_account = vesting_accounts()
+            .load(deps.as_ref().storage, (address.as_str(), DENOM_KEY, 1))
+            .unwrap();
+        assert_eq!(vesting_account.vesting_amount, Uint128::new(1000));
+        assert_eq!(vesting_account.vesting_schedule, vesting_schedule);
+
+        // The TGE tranche is claimable right away
+        let info = mock_info(address.as_str(), &[]);
+        let msg = ExecuteMsg::Claim {
+            denoms: vec![Denom::Native(DENOM.to_string())],
+            grant_id: None,
+            recipient: None,
+        };
+        let result = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            result.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: address.to_string(),
+                amount: coins(100, DENOM),
+            })
+        );
+    }
+
+    #[test]
+    fn cw20_vesting_account() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(1000);
+
+        let token = Addr::unchecked("token");
+        let address = Addr::unchecked("user1");
+        let hook = Cw20HookMsg::RegisterVestingAccount {
+            master_address: "master".to_string(),
+            address: address.to_string(),
+            vesting_schedule: VestingSchedule::LinearVesting {
+                start_time: 1000,
+                end_time: 2000,
+                vesting_amount: Uint128::new(1000),
+            },
+            revocable: true,
+            transfer_policy: None,
+        };
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "master".to_string(),
+            amount: Uint128::new(1000),
+            msg: to_json_binary(&hook).unwrap(),
+        });
+
+        // * FAIL: the token contract is not accepted, whoever the cw20 sender claims to be
+        let info = mock_info(token.as_str(), &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        match result {
+            ContractError::Cw20TokenNotAccepted { token: rejected } if rejected == token => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: only the owner accepts tokens
+        let accept = ExecuteMsg::AddCw20Tokens {
+            tokens: vec![token.to_string()],
+        };
+        let result = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("master", &[]),
+            accept.clone(),
+        )
+        .unwrap_err();
+        match result {
+            ContractError::Unauthorized {} => {}
+            e => panic!("{:?}", e),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), accept).unwrap();
+        let res: Cw20TokensResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Cw20Tokens {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.tokens, vec![token.clone()]);
+
+        // * FAIL: native funds along with the cw20 deposit
+        let info = mock_info(token.as_str(), &coins(10, DENOM));
+        let result = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        match result {
+            ContractError::FundsNotAllowed {} => {}
+            e => panic!("{:?}", e),
         };
 
-        // * FAIL for sending excess amount
-        let deposit_amount = 4000 / 1000 * vesting_amount + 1000;
-        let info = mock_info(address.as_str(), &coins(deposit_amount, DENOM));
+        let info = mock_info(token.as_str(), &[]);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let denom = Denom::Cw20(token.clone());
+        let vesting_account = vesting_accounts()
+            .load(deps.as_ref().storage, (address.as_str(), "cw20-token", 1))
+            .unwrap();
+        assert_eq!(vesting_account.vesting_denom, denom);
+        assert_eq!(
+            vested_tokens(deps.as_ref(), env.clone(), denom.clone()).unwrap(),
+            Uint128::new(1000)
+        );
+
+        // Claims are paid out with a cw20 transfer
+        env.block.time = Timestamp::from_seconds(1500);
+        let info = mock_info(address.as_str(), &[]);
+        let msg = ExecuteMsg::Claim {
+            denoms: vec![denom.clone()],
+            grant_id: None,
+            recipient: None,
+        };
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(
+            result.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: address.to_string(),
+                    amount: Uint128::new(500),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+
+        // Revocation returns the unvested cw20 tokens to the master
+        env.block.time = Timestamp::from_seconds(1600);
+        let info = mock_info("master", &[]);
+        let msg = ExecuteMsg::RevokeVestingAccount {
+            address: address.to_string(),
+            denom: denom.clone(),
+            grant_id: None,
+        };
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(
+            result.messages[1].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "master".to_string(),
+                    amount: Uint128::new(400),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+        assert_eq!(
+            vested_tokens(deps.as_ref(), env, denom).unwrap(),
+            Uint128::zero()
+        );
+    }
+
+    fn create_vesting_account(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        start_time: Option<u64>,
+        end_time: Option<u64>,
+        vesting_amount: Option<Uint128>,
+    ) {
+        let start_time = start_time.unwrap_or(1000);
+        let end_time = end_time.unwrap_or(1500);
+        let vesting_amount = vesting_amount.unwrap_or(Uint128::from(1000u64));
+        let vesting_schedule = VestingSchedule::LinearVesting {
+            start_time,
+            end_time,
+            vesting_amount,
+        };
+        let msg = ExecuteMsg::RegisterVestingAccount {
+            master_address: "master".to_string(),
+            address: info.sender.to_string(),
+            vesting_schedule,
+            revocable: true,
+            transfer_policy: None,
+        };
+        execute(deps, env, info, msg).unwrap();
+    }
+
+    #[test]
+    fn cancel_vesting_account_invalid_request() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(1000);
+
+        let address = Addr::unchecked("user1");
+
+        // * FAIL: no vesting account present
+        let info = mock_info(address.as_str(), &[]);
+        let msg = ExecuteMsg::CancelVestingAccount {
+            denom: Denom::Native(DENOM.to_string()),
+            grant_id: None,
+            vested_token_recipient: None,
+        };
+
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match result {
+            ContractError::VestingAccountNotFound { .. } => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: Invalid recipient address
+        let recipient = String::from("");
+
+        // Create a new vesting account for user1
+        let info = mock_info(address.as_str(), &coins(1000, DENOM));
+        create_vesting_account(deps.as_mut(), env.clone(), info, None, None, None);
+
+        assert!(vesting_accounts().has(deps.as_ref().storage, (address.as_str(), DENOM_KEY, 1)));
+        assert!(!vesting_accounts().has(deps.as_ref().storage, (&recipient, DENOM_KEY, 1)));
+
+        // Forward the time to 2000, so that all the tokens have vested.
+        env.block.time = Timestamp::from_seconds(2000);
+
+        // Cancel the vesting account for user1
+        let info = mock_info(address.as_str(), &[]);
+        let msg = ExecuteMsg::CancelVestingAccount {
+            denom: Denom::Native(DENOM.to_string()),
+            grant_id: None,
+            vested_token_recipient: Some(recipient),
+        };
+
+        let result = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        match result {
+            ContractError::Std(StdError::GenericErr { msg })
+                if msg.starts_with("Invalid input: human address too short") => {}
+            e => panic!("{:?}", e),
+        };
+
+        // * FAIL: funds not allowed.
+        let info = mock_info("sender", &coins(10, DENOM));
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match result {
+            ContractError::FundsNotAllowed {} => {}
+            e => panic!("{:?}", e),
+        };
+    }
+
+    #[test]
+    fn revoke_vesting_account() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(1000);
+
+        let address = Addr::unchecked("user1");
+        let info = mock_info(address.as_str(), &coins(1000, DENOM));
+        create_vesting_account(deps.as_mut(), env.clone(), info, None, None, None);
+
+        env.block.time = Timestamp::from_seconds(1100);
+        let msg = ExecuteMsg::RevokeVestingAccount {
+            address: address.to_string(),
+            denom: Denom::Native(DENOM.to_string()),
+            grant_id: None,
+        };
+
+        // * FAIL: only the master can revoke
+        let info = mock_info(address.as_str(), &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        match result {
+            ContractError::Unauthorized {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        // Vested part goes to the beneficiary, unvested part back to the master
+        let info = mock_info("master", &[]);
+        let result = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            result.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: address.to_string(),
+                amount: coins(200, DENOM),
+            })
+        );
+        assert_eq!(
+            result.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "master".to_string(),
+                amount: coins(800, DENOM),
+            })
+        );
+        assert!(!vesting_accounts().has(deps.as_ref().storage, (address.as_str(), DENOM_KEY, 1)));
+    }
+
+    #[test]
+    fn revoke_irrevocable_vesting_account() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(1000);
+
+        let address = Addr::unchecked("user1");
+        let info = mock_info("master", &coins(1000, DENOM));
         let msg = ExecuteMsg::RegisterVestingAccount {
             master_address: "master".to_string(),
             address: address.to_string(),
-            vesting_schedule: vesting_schedule.clone(),
+            vesting_schedule: VestingSchedule::LinearVesting {
+                start_time: 1000,
+                end_time: 2000,
+                vesting_amount: Uint128::new(1000),
+            },
+            revocable: false,
+            transfer_policy: None,
         };
-        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let result = vesting_account(
+            deps.as_ref(),
+            env.clone(),
+            address.to_string(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert!(!result.vestings[0].revocable);
+
+        // * FAIL: the master cannot claw back an irrevocable grant
+        let info = mock_info("master", &[]);
+        let msg = ExecuteMsg::RevokeVestingAccount {
+            address: address.to_string(),
+            denom: Denom::Native(DENOM.to_string()),
+            grant_id: Some(1),
+        };
+        let result = execute(deps.as_mut(), env, info, msg).unwrap_err();
         match result {
-            StdError::GenericErr { msg } if msg == "assert(deposit_amount = amount * ((end_time - start_time) / vesting_interval))" => {}
+            ContractError::NotRevocable { .. } => {}
             e => panic!("{:?}", e),
         };
+        assert!(vesting_accounts().has(deps.as_ref().storage, (address.as_str(), DENOM_KEY, 1)));
     }
 
     #[test]
-    fn register_vesting_account_periodic_valid_request() {
+    fn claim_invalid_request() {
         let mut env = mock_env();
         let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
 
-        env.block.time = Timestamp::from_seconds(200);
+        env.block.time = Timestamp::from_seconds(1000);
 
-        // vesting details
-        let vesting_amount = 1000;
         let address = Addr::unchecked("user1");
-        let vesting_schedule = VestingSchedule::PeriodicVesting {
-            start_time: 1000,
-            end_time: 5000,
-            vesting_interval: 1000,
-            amount: Uint128::from(vesting_amount),
+
+        // * FAIL: incorrect denoms being claimed
+        let info = mock_info(address.as_str(), &coins(1000, DENOM));
+        create_vesting_account(deps.as_mut(), env.clone(), info, None, None, None);
+
+        let msg = ExecuteMsg::Claim {
+            denoms: vec![Denom::Native("DNM".to_string())],
+            grant_id: None,
+            recipient: None,
         };
 
-        // PASS
-        let deposit_amount = 4000 / 1000 * vesting_amount;
-        let info = mock_info("user1", &coins(deposit_amount, DENOM));
-        let msg = ExecuteMsg::RegisterVestingAccount {
-            master_address: String::from("master"),
-            address: address.to_string(),
-            vesting_schedule: vesting_schedule.clone(),
+        let info = mock_info(address.as_str(), &[]);
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match result {
+            ContractError::VestingAccountNotFound { denom, grant_id }
+                if denom == "native-DNM" && grant_id.is_none() => {}
+            e => panic!("{:?}", e),
+        };
+
+        // FAIL: No vested tokens
+        let msg = ExecuteMsg::Claim {
+            denoms: vec![Denom::Native(DENOM.to_string())],
+            grant_id: None,
+            recipient: None,
         };
+
+        let info = mock_info(address.as_str(), &[]);
         let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
         assert_eq!(result.messages.len(), 0);
 
-        // Check correct update in VESTING_ACCOUNTS
-        let vesting_account = VESTING_ACCOUNTS
-            .load(deps.as_ref().storage, (address.as_str(), DENOM))
+        // FAIL: Funds not allowed
+        let info = mock_info(address.as_str(), &coins(10, DENOM));
+        let msg = ExecuteMsg::Claim {
+            denoms: vec![Denom::Native(DENOM.to_string())],
+            grant_id: None,
+            recipient: None,
+        };
+
+        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        match result {
+            ContractError::FundsNotAllowed {} => {}
+            e => panic!("{:?}", e),
+        };
+    }
+
+    // Test case for claim
+    #[test]
+    fn testing_claim() {
+        let env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+        let info = mock_info("sender", &coins(100, DENOM.to_string()));
+        let amount: u64 = 100;
+        // registering Message
+        let msg = ExecuteMsg::RegisterVestingAccount {
+            master_address: info.sender.to_string(),
+            address: info.sender.clone().into_string(),
+            vesting_schedule: VestingSchedule::LinearVesting {
+                start_time: 1662824814,
+                end_time: 1662824914,
+                vesting_amount: Uint128::from(amount),
+            },
+            revocable: true,
+            transfer_policy: None,
+        };
+        // Registering the account
+
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+        let deposit_denom = Denom::Native(info.funds[0].denom.clone());
+        let receiver_info = mock_info("recipent", &coins(100, DENOM.to_string()));
+
+        let info = mock_info("sender", &[]);
+        let res = claim(
+            deps.as_mut(),
+            env,
+            info.clone(),
+            vec![deposit_denom],
+            None,
+            Some(receiver_info.sender.clone().into_string()),
+        );
+
+        let messages: Vec<CosmosMsg> = vec![];
+        // Should return Respose
+        assert_eq!(
+            res,
+            Ok(Response::new()
+                .add_messages(messages)
+                .add_attributes(vec![("action", "claim"), ("address", info.sender.as_str())]))
+        );
+        // .add_attributes(attrs)))
+    }
+
+    #[test]
+    fn multiple_grants_per_denom() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(1000);
+
+        let address = Addr::unchecked("user1");
+
+        // Two grants of the same denom for the same beneficiary
+        let info = mock_info(address.as_str(), &coins(1000, DENOM));
+        create_vesting_account(deps.as_mut(), env.clone(), info, None, None, None);
+        let info = mock_info(address.as_str(), &coins(500, DENOM));
+        create_vesting_account(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            None,
+            Some(2000),
+            Some(Uint128::new(500)),
+        );
+
+        assert!(vesting_accounts().has(deps.as_ref().storage, (address.as_str(), DENOM_KEY, 1)));
+        assert!(vesting_accounts().has(deps.as_ref().storage, (address.as_str(), DENOM_KEY, 2)));
+        let result = vesting_account(
+            deps.as_ref(),
+            env.clone(),
+            address.to_string(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result.vestings.len(), 2);
+
+        // Claim only the second grant
+        env.block.time = Timestamp::from_seconds(1500);
+        let info = mock_info(address.as_str(), &[]);
+        let msg = ExecuteMsg::Claim {
+            denoms: vec![Denom::Native(DENOM.to_string())],
+            grant_id: Some(2),
+            recipient: None,
+        };
+        let result = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(result.messages.len(), 1);
+        assert_eq!(
+            result.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: address.to_string(),
+                amount: coins(250, DENOM),
+            })
+        );
+
+        // * FAIL: unknown grant id
+        let msg = ExecuteMsg::Claim {
+            denoms: vec![Denom::Native(DENOM.to_string())],
+            grant_id: Some(3),
+            recipient: None,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+
+        // Claim all grants of the denom
+        let msg = ExecuteMsg::Claim {
+            denoms: vec![Denom::Native(DENOM.to_string())],
+            grant_id: None,
+            recipient: None,
+        };
+        let result = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        assert_eq!(result.messages.len(), 1);
+        assert_eq!(
+            result.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: address.to_string(),
+                amount: coins(1000, DENOM),
+            })
+        );
+        // The fully claimed grant is removed
+        assert!(!vesting_accounts().has(deps.as_ref().storage, (address.as_str(), DENOM_KEY, 1)));
+
+        // Cancel the remaining grant
+        let msg = ExecuteMsg::CancelVestingAccount {
+            denom: Denom::Native(DENOM.to_string()),
+            grant_id: Some(2),
+            vested_token_recipient: None,
+        };
+        let result = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            result.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "master".to_string(),
+                amount: coins(250, DENOM),
+            })
+        );
+        assert!(!vesting_accounts().has(deps.as_ref().storage, (address.as_str(), DENOM_KEY, 2)));
+        assert_eq!(
+            DENOM_STATS
+                .load(deps.as_ref().storage, DENOM_KEY)
+                .unwrap()
+                .outstanding,
+            Uint128::zero()
+        );
+    }
+
+    #[test]
+    fn claim_all() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(1000);
+
+        let address = Addr::unchecked("user1");
+
+        // Grants in two native denoms, a cw20 token and one not yet started
+        let info = mock_info(address.as_str(), &coins(1000, DENOM));
+        create_vesting_account(deps.as_mut(), env.clone(), info, None, None, None);
+        let info = mock_info(address.as_str(), &coins(500, "ATOM"));
+        create_vesting_account(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            None,
+            None,
+            Some(Uint128::new(500)),
+        );
+        accept_cw20_token(deps.as_mut(), "token");
+        let hook = Cw20HookMsg::RegisterVestingAccount {
+            master_address: "master".to_string(),
+            address: address.to_string(),
+            vesting_schedule: VestingSchedule::LinearVesting {
+                start_time: 1000,
+                end_time: 2000,
+                vesting_amount: Uint128::new(1000),
+            },
+            revocable: true,
+            transfer_policy: None,
+        };
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "master".to_string(),
+            amount: Uint128::new(1000),
+            msg: to_json_binary(&hook).unwrap(),
+        });
+        execute(deps.as_mut(), env.clone(), mock_info("token", &[]), msg).unwrap();
+        let info = mock_info(address.as_str(), &coins(1000, DENOM));
+        create_vesting_account(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            Some(2000),
+            Some(3000),
+            None,
+        );
+
+        env.block.time = Timestamp::from_seconds(1250);
+
+        // * FAIL: funds not allowed
+        let msg = ExecuteMsg::ClaimAll {
+            recipient: None,
+            start_after: None,
+            limit: None,
+        };
+        let info = mock_info(address.as_str(), &coins(10, DENOM));
+        let result = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        match result {
+            ContractError::FundsNotAllowed {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        // An empty page claims nothing and has no cursor
+        let info = mock_info(address.as_str(), &[]);
+        let page = ExecuteMsg::ClaimAll {
+            recipient: None,
+            start_after: None,
+            limit: Some(0),
+        };
+        let result = execute(deps.as_mut(), env.clone(), info, page).unwrap();
+        assert!(result.messages.is_empty());
+        assert_eq!(
+            result.attributes,
+            vec![
+                Attribute::new("action", "claim_all"),
+                Attribute::new("address", address.as_str()),
+            ]
+        );
+
+        // Claim the first page only
+        let info = mock_info(address.as_str(), &[]);
+        let page = ExecuteMsg::ClaimAll {
+            recipient: None,
+            start_after: None,
+            limit: Some(2),
+        };
+        let result = execute(deps.as_mut(), env.clone(), info.clone(), page).unwrap();
+        assert_eq!(result.messages.len(), 2);
+        assert_eq!(
+            result.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: address.to_string(),
+                amount: coins(500, DENOM),
+            })
+        );
+        assert_eq!(
+            result.messages[1].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "token".to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: address.to_string(),
+                    amount: Uint128::new(250),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+        let cursor = result
+            .attributes
+            .iter()
+            .find(|attr| attr.key == "next_start_after")
             .unwrap();
-        assert_eq!(vesting_account.address, address.to_string());
-        assert_eq!(vesting_account.master_address, "master".to_string());
+        assert_eq!(cursor.value, "[{\"native\":\"TKN\"},1]");
+
+        // Resume after the cursor, the grant that has not started is skipped
+        let page = ExecuteMsg::ClaimAll {
+            recipient: None,
+            start_after: Some((Denom::Native(DENOM.to_string()), 1)),
+            limit: Some(2),
+        };
+        let result = execute(deps.as_mut(), env.clone(), info.clone(), page).unwrap();
+        assert_eq!(result.messages.len(), 1);
         assert_eq!(
-            vesting_account.vesting_amount,
-            Uint128::from(deposit_amount)
+            result.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: address.to_string(),
+                amount: coins(250, "ATOM"),
+            })
+        );
+
+        // Everything at once, native coins are combined into a single send
+        env.block.time = Timestamp::from_seconds(1500);
+        let result = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(result.messages.len(), 2);
+        assert_eq!(
+            result.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: address.to_string(),
+                amount: vec![coin(250, "ATOM"), coin(500, DENOM)],
+            })
         );
-        assert_eq!(vesting_account.claimed_amount, Uint128::zero());
-        assert_eq!(vesting_account.vesting_denom, DENOM.to_string());
-        assert_eq!(vesting_account.vesting_schedule, vesting_schedule);
+        assert!(!result
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "next_start_after"));
 
-        // Check correct update in VESTED_BY_DENOM
-        let denom_vested = VESTED_BY_DENOM.load(deps.as_ref().storage, DENOM).unwrap();
-        assert_eq!(denom_vested, Uint128::from(deposit_amount));
+        // Fully claimed grants are removed, the pending one remains
+        let result = vesting_account(
+            deps.as_ref(),
+            mock_env(),
+            address.to_string(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result.vestings.len(), 2);
+        assert_eq!(
+            DENOM_STATS
+                .load(deps.as_ref().storage, "native-ATOM")
+                .unwrap()
+                .outstanding,
+            Uint128::zero()
+        );
     }
 
     #[test]
-    fn register_vesting_account_linear_valid_request() {
+    fn vesting_account_query() {
         let mut env = mock_env();
         let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
 
         env.block.time = Timestamp::from_seconds(1000);
 
-        // vesting details
-        let vesting_amount = 100;
+        const DENOM2: &str = "TKN2";
         let address = Addr::unchecked("user1");
+        let vesting_amount = 1000;
+
+        // A simple testcase for the vesting_account fn.
+
+        let info = mock_info(address.as_str(), &coins(vesting_amount, DENOM));
+        create_vesting_account(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            None,
+            None,
+            Some(Uint128::from(vesting_amount)),
+        );
+
         let vesting_schedule = VestingSchedule::LinearVesting {
-            start_time: 1200,
+            start_time: 1000,
             end_time: 1500,
             vesting_amount: Uint128::from(vesting_amount),
         };
-
-        // PASS
-        let info = mock_info(address.as_str(), &coins(vesting_amount, DENOM));
-        let msg = ExecuteMsg::RegisterVestingAccount {
-            master_address: "master".to_string(),
-            address: info.sender.clone().to_string(),
-            vesting_schedule: vesting_schedule.clone(),
-        };
-
-        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
-        assert_eq!(res.messages.len(), 0);
-
-        // Check correct update in VESTING_ACCOUNTS
-        let vesting_account = VESTING_ACCOUNTS
-            .load(deps.as_ref().storage, (address.as_str(), DENOM))
-            .unwrap();
-        assert_eq!(vesting_account.address, address.to_string());
-        assert_eq!(vesting_account.claimed_amount, Uint128::zero());
-        assert_eq!(vesting_account.master_address, "master".to_string());
+        let result = vesting_account(
+            deps.as_ref(),
+            env.clone(),
+            address.to_string(),
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(result.address, address.to_string());
         assert_eq!(
-            vesting_account.vesting_amount,
-            Uint128::from(vesting_amount)
+            result.vestings[0],
+            VestingData {
+                grant_id: 1,
+                master_address: "master".to_string(),
+                address: address.to_string(),
+                vesting_denom: Denom::Native(DENOM.to_string()),
+                vesting_amount: Uint128::from(vesting_amount),
+                vested_amount: Uint128::zero(),
+                vesting_schedule: vesting_schedule.clone(),
+                claimable_amount: Uint128::zero(),
+                revocable: true,
+                transfer_policy: TransferPolicy::NonTransferable,
+                pending_transfer: None,
+            }
         );
-        assert_eq!(vesting_account.vesting_denom, DENOM.to_string());
-        assert_eq!(vesting_account.vesting_schedule, vesting_schedule);
 
-        // Check correct update in VESTED_BY_DENOM
-        let denom_vested = VESTED_BY_DENOM.load(deps.as_ref().storage, DENOM).unwrap();
-        assert_eq!(denom_vested, Uint128::from(vesting_amount));
+        let info = mock_info(address.as_str(), &coins(vesting_amount, DENOM2));
+        create_vesting_account(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            None,
+            None,
+            Some(Uint128::from(vesting_amount)),
+        );
 
-        // Should return Response
-        assert_eq!(
-            res,
-            Response::new().add_attributes(vec![
-                ("action", "register_vesting_account"),
-                ("master_address", "master"),
-                ("address", info.sender.as_str()),
-                ("vesting_denom", &to_string(DENOM).unwrap()),
-                ("vesting_amount", &info.funds[0].amount.to_string()),
-            ])
+        let result = vesting_account(
+            deps.as_ref(),
+            env.clone(),
+            address.to_string(),
+            None,
+            None,
+            None,
         )
-    }
-
-    fn create_vesting_account(
-        deps: DepsMut,
-        env: Env,
-        info: MessageInfo,
-        start_time: Option<u64>,
-        end_time: Option<u64>,
-        vesting_amount: Option<Uint128>,
-    ) {
-        let start_time = start_time.unwrap_or(1000);
-        let end_time = end_time.unwrap_or(1500);
-        let vesting_amount = vesting_amount.unwrap_or(Uint128::from(1000u64));
-        let vesting_schedule = VestingSchedule::LinearVesting {
-            start_time,
-            end_time,
-            vesting_amount,
-        };
-        let msg = ExecuteMsg::RegisterVestingAccount {
-            master_address: "master".to_string(),
-            address: info.sender.to_string(),
-            vesting_schedule,
-        };
-        execute(deps, env, info, msg).unwrap();
+        .unwrap();
+        assert_eq!(result.vestings.len(), 2);
+        assert_eq!(
+            result.vestings[1],
+            VestingData {
+                grant_id: 2,
+                master_address: "master".to_string(),
+                address: address.to_string(),
+                vesting_amount: Uint128::from(vesting_amount),
+                vesting_denom: Denom::Native(DENOM2.to_string()),
+                vesting_schedule,
+                vested_amount: Uint128::zero(),
+                claimable_amount: Uint128::zero(),
+                revocable: true,
+                transfer_policy: TransferPolicy::NonTransferable,
+                pending_transfer: None,
+            }
+        );
     }
 
     #[test]
-    fn deregister_vesting_account_invalid_request() {
+    fn vesting_account_at_time() {
         let mut env = mock_env();
         let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
 
         env.block.time = Timestamp::from_seconds(1000);
 
         let address = Addr::unchecked("user1");
-
-        // * FAIL: no vesting account present
-        let info = mock_info(address.as_str(), &[]);
-        let msg = ExecuteMsg::DeregisterVestingAccount {
-            denom: DENOM.to_string(),
-            vested_token_recipient: None,
-        };
-
-        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
-        match result {
-            StdError::GenericErr { .. } => {}
-            e => panic!("{:?}", e),
-        };
-
-        // * FAIL: Invalid recipient address
-        let recipient = String::from("");
-
-        // Create a new vesting account for user1
         let info = mock_info(address.as_str(), &coins(1000, DENOM));
         create_vesting_account(deps.as_mut(), env.clone(), info, None, None, None);
 
-        assert!(VESTING_ACCOUNTS.has(deps.as_ref().storage, (address.as_str(), DENOM)));
-        assert!(!VESTING_ACCOUNTS.has(deps.as_ref().storage, (&recipient, DENOM)));
-
-        // Forward the time to 2000, so that all the tokens have vested.
-        env.block.time = Timestamp::from_seconds(2000);
-
-        // Deregister the vesting account for user1
-        let info = mock_info(address.as_str(), &[]);
-        let msg = ExecuteMsg::DeregisterVestingAccount {
-            denom: DENOM.to_string(),
-            vested_token_recipient: Some(recipient),
+        env.block.time = Timestamp::from_seconds(1250);
+        let msg = ExecuteMsg::Claim {
+            denoms: vec![Denom::Native(DENOM.to_string())],
+            grant_id: None,
+            recipient: None,
         };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(address.as_str(), &[]),
+            msg,
+        )
+        .unwrap();
 
-        let result = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
-        match result {
-            StdError::GenericErr { msg }
-                if msg == "Invalid input: human address too short".to_string() => {}
-            e => panic!("{:?}", e),
+        let amounts = |at_time| {
+            let result = vesting_account(
+                deps.as_ref(),
+                env.clone(),
+                address.to_string(),
+                None,
+                None,
+                at_time,
+            )
+            .unwrap();
+            let data = &result.vestings[0];
+            (data.vested_amount.u128(), data.claimable_amount.u128())
         };
 
-        // * FAIL: funds not allowed.
-        let info = mock_info("sender", &coins(10, DENOM));
-        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
-        match result {
-            StdError::GenericErr { msg } if msg == "Funds not allowed." => {}
-            e => panic!("{:?}", e),
-        };
+        // Defaults to the block time
+        assert_eq!(amounts(None), (500, 0));
+        // Nothing is claimable at a past time
+        assert_eq!(amounts(Some(1100)), (200, 0));
+        assert_eq!(amounts(Some(1400)), (800, 300));
+        assert_eq!(amounts(Some(2000)), (1000, 500));
+
+        let result = vesting_accounts_by_master(
+            deps.as_ref(),
+            env.clone(),
+            "master".to_string(),
+            None,
+            None,
+            Some(1400),
+        )
+        .unwrap();
+        assert_eq!(result.vestings[0].vested_amount, Uint128::new(800));
+        let result =
+            all_vesting_accounts(deps.as_ref(), env.clone(), None, None, None, Some(1400)).unwrap();
+        assert_eq!(result.vestings[0].claimable_amount, Uint128::new(300));
+
+        // Once the block time passes it, only the vested amount is reported
+        env.block.time = Timestamp::from_seconds(1500);
+        let result = vesting_account(
+            deps.as_ref(),
+            env,
+            address.to_string(),
+            None,
+            None,
+            Some(1400),
+        )
+        .unwrap();
+        assert_eq!(result.vestings[0].vested_amount, Uint128::new(800));
+        assert_eq!(result.vestings[0].claimable_amount, Uint128::zero());
     }
 
     #[test]
-    fn claim_invalid_request() {
+    fn vesting_account_pagination() {
         let mut env = mock_env();
         let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
 
         env.block.time = Timestamp::from_seconds(1000);
 
         let address = Addr::unchecked("user1");
 
-        // * FAIL: incorrect denoms being claimed
-        let info = mock_info(address.as_str(), &coins(1000, DENOM));
-        create_vesting_account(deps.as_mut(), env.clone(), info, None, None, None);
-
-        let msg = ExecuteMsg::Claim {
-            denoms: vec!["DNM".to_string()],
-            recipient: None,
-        };
-
-        let info = mock_info(address.as_str(), &[]);
-        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
-        match result {
-            StdError::GenericErr { msg }
-                if msg == "vesting entry is not found for denom \"DNM\"" => {}
-            e => panic!("{:?}", e),
-        };
-
-        // FAIL: No vested tokens
-        let msg = ExecuteMsg::Claim {
-            denoms: vec![DENOM.to_string()],
-            recipient: None,
-        };
-
-        let info = mock_info(address.as_str(), &[]);
-        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
-        assert_eq!(result.messages.len(), 0);
+        // More denoms than fit on one page, one of them with several grants
+        let mut denoms: Vec<String> = (0..MAX_LIMIT + 5).map(|i| format!("D{:02}", i)).collect();
+        denoms.extend(vec!["D07".to_string(); 3]);
+        for denom in denoms.iter() {
+            let info = mock_info(address.as_str(), &coins(1000, denom));
+            create_vesting_account(deps.as_mut(), env.clone(), info, None, None, None);
+        }
+        let total = denoms.len();
+
+        for (limit, page_size) in [(None, DEFAULT_LIMIT), (Some(MAX_LIMIT + 10), MAX_LIMIT)] {
+            let mut seen: Vec<(Denom, u64)> = vec![];
+            let mut start_after = None;
+            loop {
+                let result = vesting_account(
+                    deps.as_ref(),
+                    env.clone(),
+                    address.to_string(),
+                    start_after,
+                    limit,
+                    None,
+                )
+                .unwrap();
+                assert!(result.vestings.len() <= page_size as usize);
+                let last = match result.vestings.last() {
+                    Some(data) => (data.vesting_denom.clone(), data.grant_id),
+                    None => break,
+                };
+                seen.extend(
+                    result
+                        .vestings
+                        .into_iter()
+                        .map(|data| (data.vesting_denom, data.grant_id)),
+                );
+                start_after = Some(last);
+            }
 
-        // FAIL: Funds not allowed
-        let info = mock_info(address.as_str(), &coins(10, DENOM));
-        let msg = ExecuteMsg::Claim {
-            denoms: vec![DENOM.to_string()],
-            recipient: None,
-        };
+            // Every grant is returned exactly once
+            assert_eq!(seen.len(), total);
+            let mut grant_ids: Vec<u64> = seen.iter().map(|(_, grant_id)| *grant_id).collect();
+            grant_ids.sort_unstable();
+            assert_eq!(grant_ids, (1..=total as u64).collect::<Vec<u64>>());
+            // Grants of the same denom are listed together, in grant id order
+            let d07: Vec<u64> = seen
+                .iter()
+                .filter(|(denom, _)| denom == &Denom::Native("D07".to_string()))
+                .map(|(_, grant_id)| *grant_id)
+                .collect();
+            assert_eq!(d07, vec![8, 36, 37, 38]);
+        }
 
-        let result = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
-        match result {
-            StdError::GenericErr { msg } if msg == "Funds not allowed." => {}
-            e => panic!("{:?}", e),
-        };
+        // Resuming in the middle of a denom
+        let result = vesting_account(
+            deps.as_ref(),
+            env,
+            address.to_string(),
+            Some((Denom::Native("D07".to_string()), 36)),
+            Some(3),
+            None,
+        )
+        .unwrap();
+        let grants: Vec<u64> = result.vestings.iter().map(|data| data.grant_id).collect();
+        assert_eq!(grants, vec![37, 38, 9]);
     }
 
-    // Test case for claim
     #[test]
-    fn testing_claim() {
-        let env = mock_env();
+    fn vesting_accounts_by_master_query() {
+        let mut env = mock_env();
         let mut deps = mock_dependencies();
-        let info = mock_info("sender", &coins(100, DENOM.to_string()));
-        let amount: u64 = 100;
-        // registering Message
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(1000);
+
+        // "master" funds two grants for user1 and one for user2, "treasury" one for user1
+        for (user, amount) in [("user1", 100u128), ("user2", 200), ("user1", 300)] {
+            let info = mock_info(user, &coins(amount, DENOM));
+            create_vesting_account(
+                deps.as_mut(),
+                env.clone(),
+                info,
+                None,
+                None,
+                Some(Uint128::new(amount)),
+            );
+        }
         let msg = ExecuteMsg::RegisterVestingAccount {
-            master_address: info.sender.to_string(),
-            address: info.sender.clone().into_string(),
+            master_address: "treasury".to_string(),
+            address: "user1".to_string(),
             vesting_schedule: VestingSchedule::LinearVesting {
-                start_time: 1662824814,
-                end_time: 1662824914,
-                vesting_amount: Uint128::from(amount),
+                start_time: 1000,
+                end_time: 1500,
+                vesting_amount: Uint128::new(400),
             },
+            revocable: true,
+            transfer_policy: None,
         };
-        // Registering the account
-
-        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
-        let deposit_denom = info.funds[0].denom.clone();
-        let receiver_info = mock_info("recipent", &coins(100, DENOM.to_string()));
-
-        let info = mock_info("sender", &[]);
-        let res = claim(
+        execute(
             deps.as_mut(),
-            env,
-            info.clone(),
-            vec![deposit_denom],
-            Some(receiver_info.sender.clone().into_string()),
+            env.clone(),
+            mock_info("treasury", &coins(400, DENOM)),
+            msg,
+        )
+        .unwrap();
+
+        let query = |deps: Deps, master: &str, start_after, limit| {
+            vesting_accounts_by_master(
+                deps,
+                mock_env(),
+                master.to_string(),
+                start_after,
+                limit,
+                None,
+            )
+            .unwrap()
+        };
+
+        let result = query(deps.as_ref(), "master", None, None);
+        assert_eq!(result.master, "master");
+        let grants: Vec<(String, u64)> = result
+            .vestings
+            .iter()
+            .map(|data| (data.address.clone(), data.grant_id))
+            .collect();
+        assert_eq!(
+            grants,
+            vec![
+                ("user1".to_string(), 1),
+                ("user1".to_string(), 3),
+                ("user2".to_string(), 2)
+            ]
         );
 
-        let messages: Vec<CosmosMsg> = vec![];
-        // Should return Respose
+        let result = query(deps.as_ref(), "treasury", None, None);
+        assert_eq!(result.vestings.len(), 1);
+        assert_eq!(result.vestings[0].grant_id, 4);
+        assert_eq!(result.vestings[0].vesting_amount, Uint128::new(400));
+
+        // Paginate with the (address, denom, grant_id) cursor
+        let result = query(deps.as_ref(), "master", None, Some(2));
+        assert_eq!(result.vestings.len(), 2);
+        let last = &result.vestings[1];
+        let cursor = Some((
+            last.address.clone(),
+            last.vesting_denom.clone(),
+            last.grant_id,
+        ));
+        let result = query(deps.as_ref(), "master", cursor, Some(2));
+        assert_eq!(result.vestings.len(), 1);
+        assert_eq!(result.vestings[0].grant_id, 2);
+
+        // Revoked grants drop out of the index
+        let msg = ExecuteMsg::RevokeVestingAccount {
+            address: "user1".to_string(),
+            denom: Denom::Native(DENOM.to_string()),
+            grant_id: None,
+        };
+        execute(deps.as_mut(), env, mock_info("master", &[]), msg).unwrap();
+        let result = query(deps.as_ref(), "master", None, None);
+        assert_eq!(result.vestings.len(), 1);
+        assert_eq!(result.vestings[0].address, "user2");
         assert_eq!(
-            res,
-            Ok(Response::new()
-                .add_messages(messages)
-                .add_attributes(vec![("action", "claim"), ("address", info.sender.as_str())]))
+            query(deps.as_ref(), "treasury", None, None).vestings.len(),
+            1
         );
-        // .add_attributes(attrs)))
     }
 
     #[test]
-    fn vesting_account_query() {
+    fn all_vesting_accounts_query() {
         let mut env = mock_env();
         let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
 
         env.block.time = Timestamp::from_seconds(1000);
 
-        const DENOM2: &str = "TKN2";
-        let address = Addr::unchecked("user1");
-        let vesting_amount = 1000;
-
-        // A simple testcase for the vesting_account fn.
-
-        let info = mock_info(address.as_str(), &coins(vesting_amount, DENOM));
-        create_vesting_account(
-            deps.as_mut(),
-            env.clone(),
-            info,
-            None,
-            None,
-            Some(Uint128::from(vesting_amount)),
-        );
+        for (user, denom) in [("user1", DENOM), ("user2", "ATOM"), ("user1", "ATOM")] {
+            let info = mock_info(user, &coins(1000, denom));
+            create_vesting_account(deps.as_mut(), env.clone(), info, None, None, None);
+        }
 
-        let vesting_schedule = VestingSchedule::LinearVesting {
-            start_time: 1000,
-            end_time: 1500,
-            vesting_amount: Uint128::from(vesting_amount),
+        let grants = |response: AllVestingAccountsResponse| -> Vec<(String, u64)> {
+            response
+                .vestings
+                .into_iter()
+                .map(|data| (data.address, data.grant_id))
+                .collect()
         };
+
         let result =
-            vesting_account(deps.as_ref(), env.clone(), address.to_string(), None, None).unwrap();
-        assert_eq!(result.address, address.to_string());
+            all_vesting_accounts(deps.as_ref(), env.clone(), None, None, None, None).unwrap();
         assert_eq!(
-            result.vestings[0],
-            VestingData {
-                master_address: "master".to_string(),
-                vesting_denom: DENOM.to_string(),
-                vesting_amount: Uint128::from(vesting_amount),
-                vested_amount: Uint128::zero(),
-                vesting_schedule: vesting_schedule.clone(),
-                claimable_amount: Uint128::zero()
-            }
+            grants(result),
+            vec![
+                ("user1".to_string(), 1),
+                ("user1".to_string(), 3),
+                ("user2".to_string(), 2)
+            ]
         );
 
-        let info = mock_info(address.as_str(), &coins(vesting_amount, DENOM2));
-        create_vesting_account(
-            deps.as_mut(),
+        // Page through the grants of one denom
+        let atom = Denom::Native("ATOM".to_string());
+        let result = all_vesting_accounts(
+            deps.as_ref(),
             env.clone(),
-            info,
             None,
+            Some(1),
+            Some(atom.clone()),
             None,
-            Some(Uint128::from(vesting_amount)),
-        );
+        )
+        .unwrap();
+        assert_eq!(grants(result), vec![("user1".to_string(), 3)]);
+        let result = all_vesting_accounts(
+            deps.as_ref(),
+            env.clone(),
+            Some(("user1".to_string(), atom.clone(), 3)),
+            Some(1),
+            Some(atom.clone()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(grants(result), vec![("user2".to_string(), 2)]);
+        let result = all_vesting_accounts(
+            deps.as_ref(),
+            env.clone(),
+            Some(("user2".to_string(), atom.clone(), 2)),
+            Some(1),
+            Some(atom),
+            None,
+        )
+        .unwrap();
+        assert!(result.vestings.is_empty());
 
-        let result =
-            vesting_account(deps.as_ref(), env.clone(), address.to_string(), None, None).unwrap();
-        assert_eq!(result.vestings.len(), 2);
-        assert_eq!(
-            result.vestings[1],
-            VestingData {
-                master_address: "master".to_string(),
-                vesting_amount: Uint128::from(vesting_amount),
-                vesting_denom: DENOM2.to_string(),
-                vesting_schedule: vesting_schedule,
-                vested_amount: Uint128::zero(),
-                claimable_amount: Uint128::zero(),
-            }
-        );
+        // Unknown denoms have no grants
+        let result = all_vesting_accounts(
+            deps.as_ref(),
+            env,
+            None,
+            None,
+            Some(Denom::Cw20(Addr::unchecked("token"))),
+            None,
+        )
+        .unwrap();
+        assert!(result.vestings.is_empty());
     }
 
     // testcase for Query to get vesting account
@@ -933,6 +5959,7 @@ mod tests {
     fn testing_vesting_account() {
         let env = mock_env();
         let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
         let info = mock_info("sender", &coins(100, DENOM.to_string()));
         let amount: u64 = 100;
         let msg = ExecuteMsg::RegisterVestingAccount {
@@ -943,6 +5970,8 @@ mod tests {
                 end_time: 1662824914,
                 vesting_amount: Uint128::from(amount),
             },
+            revocable: true,
+            transfer_policy: None,
         };
         // Registering the Account
         let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
@@ -950,16 +5979,18 @@ mod tests {
         // Query Message
         let _querymsg = QueryMsg::VestingAccount {
             address: info.sender.to_string(),
-            start_after: Some(deposit_denom.clone()),
+            start_after: Some((deposit_denom.clone(), 1)),
             limit: Some(0),
+            at_time: None,
         };
         // running Query function
         let res = vesting_account(
             deps.as_ref(),
             env,
             info.sender.clone().into_string(),
-            Some(deposit_denom),
+            Some((deposit_denom, 1)),
             Some(0),
+            None,
         )
         .unwrap();
         // Should return VEstingAccountrespose.
@@ -972,11 +6003,349 @@ mod tests {
         )
     }
 
+    #[test]
+    fn denom_stats_query() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(1000);
+
+        for (user, amount, denom) in [
+            ("user1", 1000, DENOM),
+            ("user2", 1000, DENOM),
+            ("user1", 500, "ATOM"),
+        ] {
+            let info = mock_info(user, &coins(amount, denom));
+            create_vesting_account(
+                deps.as_mut(),
+                env.clone(),
+                info,
+                None,
+                None,
+                Some(Uint128::new(amount)),
+            );
+        }
+
+        let tkn = Denom::Native(DENOM.to_string());
+        let atom = Denom::Native("ATOM".to_string());
+        let claim = ExecuteMsg::Claim {
+            denoms: vec![tkn.clone()],
+            grant_id: None,
+            recipient: None,
+        };
+
+        // Partial claim keeps the grant active
+        env.block.time = Timestamp::from_seconds(1100);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("user1", &[]),
+            claim.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            denom_stats(deps.as_ref(), tkn.clone()).unwrap().stats,
+            DenomStats {
+                deposited: Uint128::new(2000),
+                claimed: Uint128::new(200),
+                returned: Uint128::zero(),
+                active_grants: 2,
+                outstanding: Uint128::new(1800),
+            }
+        );
+
+        // Revocation pays the vested part and returns the rest to the master
+        let msg = ExecuteMsg::RevokeVestingAccount {
+            address: "user2".to_string(),
+            denom: tkn.clone(),
+            grant_id: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("master", &[]), msg).unwrap();
+
+        // Final claim removes the grant
+        env.block.time = Timestamp::from_seconds(1500);
+        execute(deps.as_mut(), env.clone(), mock_info("user1", &[]), claim).unwrap();
+
+        let tkn_stats = DenomStats {
+            deposited: Uint128::new(2000),
+            claimed: Uint128::new(1200),
+            returned: Uint128::new(800),
+            active_grants: 0,
+            outstanding: Uint128::zero(),
+        };
+        let atom_stats = DenomStats {
+            deposited: Uint128::new(500),
+            claimed: Uint128::zero(),
+            returned: Uint128::zero(),
+            active_grants: 1,
+            outstanding: Uint128::new(500),
+        };
+        assert_eq!(
+            denom_stats(deps.as_ref(), tkn.clone()).unwrap().stats,
+            tkn_stats
+        );
+        assert_eq!(
+            vested_tokens(deps.as_ref(), env.clone(), atom.clone()).unwrap(),
+            Uint128::new(500)
+        );
+
+        // Unknown denoms have empty stats
+        let result = denom_stats(deps.as_ref(), Denom::Native("DNM".to_string())).unwrap();
+        assert_eq!(result.stats, DenomStats::default());
+
+        let result = all_denom_stats(deps.as_ref(), None, None).unwrap();
+        assert_eq!(
+            result.stats,
+            vec![
+                DenomStatsResponse {
+                    denom: atom.clone(),
+                    stats: atom_stats,
+                },
+                DenomStatsResponse {
+                    denom: tkn.clone(),
+                    stats: tkn_stats.clone(),
+                },
+            ]
+        );
+        let result = all_denom_stats(deps.as_ref(), Some(atom), None).unwrap();
+        assert_eq!(
+            result.stats,
+            vec![DenomStatsResponse {
+                denom: tkn,
+                stats: tkn_stats,
+            }]
+        );
+    }
+
+    #[test]
+    fn vesting_timeline_query() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(1000);
+
+        let address = Addr::unchecked("user1");
+        let info = mock_info(address.as_str(), &coins(1000, DENOM));
+        create_vesting_account(deps.as_mut(), env.clone(), info, None, None, None);
+        let info = mock_info(address.as_str(), &coins(500, DENOM));
+        create_vesting_account(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            None,
+            Some(2000),
+            Some(Uint128::new(500)),
+        );
+
+        env.block.time = Timestamp::from_seconds(1250);
+        let msg = ExecuteMsg::ClaimAll {
+            recipient: None,
+            start_after: None,
+            limit: None,
+        };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(address.as_str(), &[]),
+            msg,
+        )
+        .unwrap();
+
+        let denom = Denom::Native(DENOM.to_string());
+        let timeline = |grant_id, from, to, step| {
+            vesting_timeline(
+                deps.as_ref(),
+                &env,
+                address.to_string(),
+                denom.clone(),
+                grant_id,
+                from,
+                to,
+                step,
+            )
+        };
+        let amounts = |response: VestingTimelineResponse| -> Vec<(u64, u128, u128)> {
+            response
+                .points
+                .into_iter()
+                .map(|point| {
+                    (
+                        point.time,
+                        point.vested_amount.u128(),
+                        point.claimable_amount.u128(),
+                    )
+                })
+                .collect()
+        };
+
+        // The last point is at `to` even if it is not a multiple of `step`
+        let result = timeline(None, 1000, 1600, 250).unwrap();
+        assert_eq!(
+            amounts(result),
+            vec![
+                (1000, 0, 0),
+                (1250, 625, 0),
+                (1500, 1250, 625),
+                (1600, 1300, 675)
+            ]
+        );
+
+        let result = timeline(Some(2), 1000, 1500, 250).unwrap();
+        assert_eq!(
+            amounts(result),
+            vec![(1000, 0, 0), (1250, 125, 0), (1500, 250, 125)]
+        );
+
+        // Unknown grants project nothing
+        let result = timeline(Some(3), 1000, 1500, 250).unwrap();
+        assert_eq!(
+            amounts(result),
+            vec![(1000, 0, 0), (1250, 0, 0), (1500, 0, 0)]
+        );
+
+        // * FAIL: invalid ranges
+        timeline(None, 1000, 1500, 0).unwrap_err();
+        timeline(None, 1500, 1000, 100).unwrap_err();
+        timeline(None, 0, MAX_TIMELINE_POINTS, 1).unwrap_err();
+        timeline(None, 0, MAX_TIMELINE_POINTS - 1, 1).unwrap();
+        // the final `to` point counts towards the cap
+        timeline(None, 0, 2 * MAX_TIMELINE_POINTS - 1, 2).unwrap_err();
+        timeline(None, 0, 2 * MAX_TIMELINE_POINTS - 2, 2).unwrap();
+
+        // Steps wider than a 32 bit usize do not wrap
+        let step = u32::MAX as u64 + 1;
+        let result = timeline(None, 1000, 1000 + 2 * step, step).unwrap();
+        assert_eq!(
+            amounts(result),
+            vec![
+                (1000, 0, 0),
+                (1000 + step, 1500, 875),
+                (1000 + 2 * step, 1500, 875)
+            ]
+        );
+        let result = timeline(None, u64::MAX - 1, u64::MAX, step).unwrap();
+        assert_eq!(
+            amounts(result),
+            vec![(u64::MAX - 1, 1500, 875), (u64::MAX, 1500, 875)]
+        );
+
+        // Nothing is claimable at points before the block time
+        let mut later = env.clone();
+        later.block.time = Timestamp::from_seconds(1600);
+        let result = vesting_timeline(
+            deps.as_ref(),
+            &later,
+            address.to_string(),
+            denom.clone(),
+            None,
+            1500,
+            1700,
+            100,
+        )
+        .unwrap();
+        assert_eq!(
+            amounts(result),
+            vec![(1500, 1250, 0), (1600, 1300, 675), (1700, 1350, 725)]
+        );
+    }
+
+    #[test]
+    fn solvency_and_sweep_surplus() {
+        let mut env = mock_env();
+        let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
+
+        env.block.time = Timestamp::from_seconds(1000);
+
+        let info = mock_info("user1", &coins(1000, DENOM));
+        create_vesting_account(deps.as_mut(), env.clone(), info, None, None, None);
+        let denom = Denom::Native(DENOM.to_string());
+
+        // Balance matches the liabilities
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(1000, DENOM));
+        let result = solvency(deps.as_ref(), &env, denom.clone()).unwrap();
+        assert_eq!(result.outstanding, Uint128::new(1000));
+        assert_eq!(result.surplus, Uint128::zero());
+        assert_eq!(result.deficit, Uint128::zero());
+
+        let msg = ExecuteMsg::SweepSurplus {
+            denom: denom.clone(),
+            recipient: Some("treasury".to_string()),
+        };
+        let result = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        match result {
+            ContractError::NoSurplus { denom } if denom == DENOM_KEY => {}
+            e => panic!("{:?}", e),
+        };
+
+        // Tokens sent to the contract directly are surplus
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(1200, DENOM));
+        let result = solvency(deps.as_ref(), &env, denom.clone()).unwrap();
+        assert_eq!(result.balance, Uint128::new(1200));
+        assert_eq!(result.surplus, Uint128::new(200));
+
+        // * FAIL: only the owner can sweep
+        let result = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("user1", &[]),
+            msg.clone(),
+        )
+        .unwrap_err();
+        match result {
+            ContractError::Unauthorized {} => {}
+            e => panic!("{:?}", e),
+        };
+
+        let result = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+        assert_eq!(
+            result.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "treasury".to_string(),
+                amount: coins(200, DENOM),
+            })
+        );
+
+        // Missing funds are reported as deficit
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(900, DENOM));
+        let result = solvency(deps.as_ref(), &env, denom).unwrap();
+        assert_eq!(result.surplus, Uint128::zero());
+        assert_eq!(result.deficit, Uint128::new(100));
+
+        // cw20 balances are queried from the token contract
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == "token" => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&Cw20BalanceResponse {
+                        balance: Uint128::new(50),
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => panic!("unexpected query {:?}", query),
+        });
+        let result = solvency(deps.as_ref(), &env, Denom::Cw20(Addr::unchecked("token"))).unwrap();
+        assert_eq!(result.outstanding, Uint128::zero());
+        assert_eq!(result.surplus, Uint128::new(50));
+    }
+
     // test case for query to get vesting Tokens.
     #[test]
     fn testing_vesting_tokens() {
         let env = mock_env();
         let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
         let info = mock_info("sender", &coins(100, DENOM.to_string()));
         let amount: u64 = 100;
         // register Message
@@ -988,6 +6357,8 @@ mod tests {
                 end_time: 1662824914,
                 vesting_amount: Uint128::from(amount),
             },
+            revocable: true,
+            transfer_policy: None,
         };
 
         // Registering Accounts.
@@ -995,22 +6366,29 @@ mod tests {
         let _deposit_denom = Denom::Native(info.funds[0].denom.clone());
 
         // Running VestedTokens Query
-        let res = vested_tokens(deps.as_ref(), env, info.funds[0].denom.clone()).unwrap();
+        let res = vested_tokens(
+            deps.as_ref(),
+            env,
+            Denom::Native(info.funds[0].denom.clone()),
+        )
+        .unwrap();
         assert_eq!(res, Uint128::from(amount));
     }
 
     #[test]
-    fn test_deregister_vesting_account_mapping_check() {
+    fn test_cancel_vesting_account_mapping_check() {
         let mut env = mock_env();
         env.block.height = 100;
         env.block.time = Timestamp::from_seconds(100);
 
         let mut deps = mock_dependencies();
+        instantiate_contract(deps.as_mut());
 
         // Create a vesting account
         let master_address = Addr::unchecked("master_address");
         let user_address = Addr::unchecked("user_address");
         let denom = String::from("uToken");
+        let denom_key = denom_to_key(&Denom::Native(denom.clone()));
         let deposit = coin(1000, &denom);
         let vesting_schedule = VestingSchedule::LinearVesting {
             start_time: 110,
@@ -1023,23 +6401,25 @@ mod tests {
             env.clone(),
             master_address.to_string(),
             user_address.to_string(),
-            denom.to_string(),
-            deposit.clone(),
+            Denom::Native(denom.clone()),
+            deposit.amount,
             vesting_schedule,
+            true,
+            TransferPolicy::NonTransferable,
         )
         .unwrap();
         assert_eq!(result.messages.len(), 0);
-        assert_eq!(result.attributes.len(), 5);
+        assert_eq!(result.attributes.len(), 7);
 
         // Forward time to vest tokens
         env.block.height = 120;
         env.block.time = Timestamp::from_seconds(120);
 
         // Total vested tokens = 10th of the initial deposit
-        let vesting_details = VESTING_ACCOUNTS
+        let vesting_details = vesting_accounts()
             .load(
                 deps.as_ref().storage,
-                (user_address.as_str(), denom.as_str()),
+                (user_address.as_str(), denom_key.as_str(), 1),
             )
             .unwrap();
         assert_eq!(
@@ -1051,11 +6431,17 @@ mod tests {
             100u128
         );
 
-        // Deregister
+        // Cancel
         let info = mock_info(user_address.as_str(), &[]);
-        let result =
-            deregister_vesting_account(deps.as_mut(), env.clone(), info, denom.clone(), None)
-                .unwrap();
+        let result = cancel_vesting_account(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            Denom::Native(denom.clone()),
+            None,
+            None,
+        )
+        .unwrap();
         assert_eq!(result.messages.len(), 2);
         assert_eq!(
             result.messages[0].msg,
@@ -1072,10 +6458,152 @@ mod tests {
             })
         );
 
-        assert!(!VESTING_ACCOUNTS.has(deps.as_ref().storage, (user_address.as_str(), &denom)));
-        assert!(VESTED_BY_DENOM.has(deps.as_ref().storage, &denom));
+        assert!(!vesting_accounts().has(
+            deps.as_ref().storage,
+            (user_address.as_str(), &denom_key, 1)
+        ));
+        assert!(DENOM_STATS.has(deps.as_ref().storage, &denom_key));
 
-        let vested_tokens = VESTED_BY_DENOM.load(deps.as_ref().storage, &denom).unwrap();
+        let vested_tokens = DENOM_STATS
+            .load(deps.as_ref().storage, &denom_key)
+            .unwrap()
+            .outstanding;
         assert_eq!(vested_tokens.u128(), 0u128);
     }
+
+    #[test]
+    fn migrate_from_legacy_layout() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        // the baseline layout only stored the contract version and grants
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.0.0").unwrap();
+        let schedule = VestingSchedule::LinearVesting {
+            start_time: 100,
+            end_time: 110,
+            vesting_amount: Uint128::new(1000000u128),
+        };
+        for (address, claimed) in [("addr0001", 0u128), ("addr0002", 300000u128)] {
+            LEGACY_VESTING_ACCOUNTS
+                .save(
+                    deps.as_mut().storage,
+                    (address, DENOM),
+                    &LegacyVestingAccount {
+                        master_address: "addr0003".to_string(),
+                        address: address.to_string(),
+                        vesting_denom: DENOM.to_string(),
+                        vesting_amount: Uint128::new(1000000u128),
+                        vesting_schedule: schedule.clone(),
+                        claimed_amount: Uint128::new(claimed),
+                    },
+                )
+                .unwrap();
+        }
+        LEGACY_VESTED_BY_DENOM
+            .save(deps.as_mut().storage, DENOM, &Uint128::new(2000000u128))
+            .unwrap();
+
+        // * FAIL: the owner cannot be derived from the legacy layout
+        let msg = MigrateMsg {
+            owner: None,
+            app_id: None,
+        };
+        match migrate(deps.as_mut(), env.clone(), msg).unwrap_err() {
+            ContractError::MissingMigrationOwner {} => {}
+            e => panic!("{:?}", e),
+        }
+
+        let msg = MigrateMsg {
+            owner: Some("owner".to_string()),
+            app_id: Some(7),
+        };
+        migrate(deps.as_mut(), env.clone(), msg).unwrap();
+        let config: Config =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(
+            config,
+            Config {
+                owner: Addr::unchecked("owner"),
+                app_id: 7,
+                vesting_contract: None,
+                open_registration: true,
+                distribution_bounty: Decimal::zero(),
+            }
+        );
+
+        // the configuration is left untouched once it exists
+        let msg = MigrateMsg {
+            owner: Some("addr0002".to_string()),
+            app_id: None,
+        };
+        migrate(deps.as_mut(), env.clone(), msg).unwrap();
+        let migrated: Config =
+            from_json(query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(migrated, config);
+
+        // legacy grants are re-keyed with ids in storage order
+        for (grant_id, address, claimed) in [(1, "addr0001", 0u128), (2, "addr0002", 300000u128)] {
+            assert_eq!(
+                vesting_accounts()
+                    .load(&deps.storage, (address, DENOM_KEY, grant_id))
+                    .unwrap(),
+                VestingAccount {
+                    grant_id,
+                    master_address: "addr0003".to_string(),
+                    address: address.to_string(),
+                    vesting_denom: Denom::Native(DENOM.to_string()),
+                    vesting_amount: Uint128::new(1000000u128),
+                    vesting_schedule: schedule.clone(),
+                    claimed_amount: Uint128::new(claimed),
+                    revocable: false,
+                    transfer_policy: TransferPolicy::NonTransferable,
+                    pending_transfer: None,
+                    approvals: vec![],
+                }
+            );
+        }
+        assert!(!LEGACY_VESTING_ACCOUNTS.has(&deps.storage, ("addr0001", DENOM)));
+        assert_eq!(
+            vesting_accounts()
+                .range(&deps.storage, None, None, Order::Ascending)
+                .count(),
+            2
+        );
+
+        // the denom stats are rebuilt from the migrated grants
+        let stats: DenomStatsResponse = from_json(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::DenomStats {
+                    denom: Denom::Native(DENOM.to_string()),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            stats.stats,
+            DenomStats {
+                deposited: Uint128::new(2000000u128),
+                claimed: Uint128::new(300000u128),
+                returned: Uint128::zero(),
+                active_grants: 2,
+                outstanding: Uint128::new(1700000u128),
+            }
+        );
+        assert!(!LEGACY_VESTED_BY_DENOM.has(&deps.storage, DENOM));
+        let solvency: SolvencyResponse = from_json(
+            query(
+                deps.as_ref(),
+                env,
+                QueryMsg::Solvency {
+                    denom: Denom::Native(DENOM.to_string()),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(solvency.outstanding, Uint128::new(1700000u128));
+    }
 }
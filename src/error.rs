@@ -0,0 +1,165 @@
+use cosmwasm_std::{Decimal, OverflowError, StdError, Uint128};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Funds not allowed")]
+    FundsNotAllowed {},
+
+    #[error("Deposits of cw20 token {token} are not accepted")]
+    Cw20TokenNotAccepted { token: String },
+
+    #[error("Must deposit exactly one type of token, got {count}")]
+    InvalidDepositCount { count: usize },
+
+    #[error("Deposit amount must not be zero")]
+    ZeroDeposit {},
+
+    #[error("{0}")]
+    Schedule(#[from] ScheduleError),
+
+    #[error("No vesting accounts to register")]
+    EmptyBatch {},
+
+    #[error("Deposit of {deposit_amount} {denom} does not match the total vesting amount {vesting_amount}")]
+    BatchDepositMismatch {
+        denom: String,
+        deposit_amount: Uint128,
+        vesting_amount: Uint128,
+    },
+
+    #[error("Vesting account not found for denom {denom}")]
+    VestingAccountNotFound {
+        denom: String,
+        grant_id: Option<u64>,
+    },
+
+    #[error("Vesting account is not revocable for denom {denom}")]
+    NotRevocable {
+        denom: String,
+        grant_id: Option<u64>,
+    },
+
+    #[error("Vesting account is not transferable for denom {denom}")]
+    NotTransferable {
+        denom: String,
+        grant_id: Option<u64>,
+    },
+
+    #[error("Vesting account cannot be transferred to its current address")]
+    InvalidTransferRecipient {},
+
+    #[error("Grant {grant_id} has fully vested, nothing is left to split")]
+    FullyVested { grant_id: u64 },
+
+    #[error("No pending transfer for grant {grant_id}")]
+    NoPendingTransfer { grant_id: u64 },
+
+    #[error("Pending transfer of grant {grant_id} is to {pending_transfer}")]
+    PendingTransferMismatch {
+        grant_id: u64,
+        pending_transfer: String,
+    },
+
+    #[error("Approval has already expired")]
+    Expired {},
+
+    #[error("Invalid token id {token_id}, token ids are grant ids")]
+    InvalidTokenId { token_id: String },
+
+    #[error("No grant with token id {token_id}")]
+    TokenNotFound { token_id: String },
+
+    #[error("Distribution bounty {bounty} exceeds the maximum of {max}")]
+    InvalidDistributionBounty { bounty: Decimal, max: Decimal },
+
+    #[error("No surplus to sweep for denom {denom}")]
+    NoSurplus { denom: String },
+
+    #[error("Cannot migrate from contract {contract}")]
+    InvalidMigrationContract { contract: String },
+
+    #[error("Cannot migrate from newer version {version}")]
+    InvalidMigrationVersion { version: String },
+
+    #[error("Migrating from a version without configuration requires an owner")]
+    MissingMigrationOwner {},
+}
+
+/// Reasons a vesting schedule is rejected, shared by registration and the
+/// `ValidateVestingSchedule` query.
+#[derive(Error, Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleError {
+    #[error("Deposit amount {deposit_amount} does not match the vesting amount {vesting_amount}")]
+    DepositMismatch {
+        deposit_amount: Uint128,
+        vesting_amount: Uint128,
+    },
+
+    #[error("Cannot make zero token vesting account")]
+    ZeroVestingAmount {},
+
+    #[error("Vesting amount overflows")]
+    VestingAmountOverflow {},
+
+    #[error("Schedule extension does not apply to this vesting schedule")]
+    UnsupportedExtension {},
+
+    #[error("Top-up amount {amount} cannot be split evenly over {intervals} intervals")]
+    IndivisibleTopUp { amount: Uint128, intervals: u64 },
+
+    #[error(
+        "Split amount {amount} must be above zero and below the vesting amount {vesting_amount}"
+    )]
+    InvalidSplit {
+        amount: Uint128,
+        vesting_amount: Uint128,
+    },
+
+    #[error("Split amount {amount} cannot be spread evenly over {intervals} intervals")]
+    IndivisibleSplit { amount: Uint128, intervals: u64 },
+
+    #[error("Vesting start time {start_time} is before the block time {block_time}")]
+    StartTimeInPast { start_time: u64, block_time: u64 },
+
+    #[error("Vesting end time {end_time} must be after the start time {start_time}")]
+    InvalidEndTime { start_time: u64, end_time: u64 },
+
+    #[error(
+        "Cliff time {cliff_time} must lie between start time {start_time} and end time {end_time}"
+    )]
+    InvalidCliffTime {
+        start_time: u64,
+        end_time: u64,
+        cliff_time: u64,
+    },
+
+    #[error("Vesting interval must not be zero")]
+    ZeroVestingInterval {},
+
+    #[error(
+        "Vesting period {period} is not a multiple of the vesting interval {vesting_interval}"
+    )]
+    IndivisibleVestingPeriod { period: u64, vesting_interval: u64 },
+
+    #[error("Vesting schedule must have at least one unlock point")]
+    NoUnlockPoints {},
+
+    #[error("Unlock point {index} is not after the previous unlock point")]
+    UnorderedUnlockPoints { index: usize },
+
+    #[error("Unlock point {index} unlocks less than the previous unlock point")]
+    DecreasingUnlockPoints { index: usize },
+}
@@ -1,10 +1,21 @@
-use cosmwasm_std::{Addr, StdResult, Uint128};
-use cw20::Denom;
+use crate::error::ScheduleError;
+use crate::state::DenomStats;
+use cosmwasm_std::{to_json_binary, Addr, Binary, CosmosMsg, Decimal, StdResult, Uint128, WasmMsg};
+use cw20::{Cw20ReceiveMsg, Denom, Expiration};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, JsonSchema)]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+    /// Defaults to the sender
+    pub owner: Option<String>,
+    pub app_id: u64,
+    pub vesting_contract: Option<String>,
+    /// Whether anyone may register vesting accounts, or only allowlisted creators
+    pub open_registration: bool,
+    /// Share of each automatic distribution paid to the caller, defaults to zero
+    pub distribution_bounty: Option<Decimal>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -16,34 +27,395 @@ pub enum ExecuteMsg {
         master_address: String,
         address: String,
         vesting_schedule: VestingSchedule,
+        /// Whether the master may revoke the grant later on
+        revocable: bool,
+        /// Whether the beneficiary may move the grant to another address.
+        /// Defaults to `NonTransferable`.
+        transfer_policy: Option<TransferPolicy>,
+    },
+    /// Register many vesting accounts at once. The sent funds must match the total
+    /// of all schedules, per denom.
+    RegisterVestingAccounts {
+        accounts: Vec<VestingAccountRegistration>,
     },
+    /// Register a vesting account funded with cw20 tokens, see `Cw20HookMsg`.
+    Receive(Cw20ReceiveMsg),
 
-    /// Deregister vesting account for the (sender, denom) pair.
-    DeregisterVestingAccount {
-        denom: String,
-        vested_token_recipient: Option<String>,
+    /////////////////////////
+    /// Master Operations ///
+    /////////////////////////
+    /// Deposit more tokens into grant `grant_id` of `address`, extending its schedule.
+    /// The denom of the grant is the denom of the sent funds. Only the master can top up.
+    TopUpVestingAccount {
+        address: String,
+        grant_id: u64,
+        schedule_extension: ScheduleExtension,
+    },
+    /// Carry out the pending transfer of a `MasterApproval` grant. Only the master can approve,
+    /// and only if the grant is still pending a transfer to `new_address`.
+    ApproveVestingTransfer {
+        address: String,
+        denom: Denom,
+        grant_id: u64,
+        new_address: String,
+    },
+
+    ////////////////////////
+    /// Owner Operations ///
+    ////////////////////////
+    UpdateConfig {
+        owner: Option<String>,
+        app_id: Option<u64>,
+        vesting_contract: Option<String>,
+        open_registration: Option<bool>,
+        distribution_bounty: Option<Decimal>,
+    },
+    /// Allow the given addresses to register vesting accounts.
+    AddCreators { creators: Vec<String> },
+    /// Remove the given addresses from the creator allowlist.
+    RemoveCreators { creators: Vec<String> },
+    /// Accept deposits of the given cw20 token contracts.
+    AddCw20Tokens { tokens: Vec<String> },
+    /// Stop accepting deposits of the given cw20 token contracts.
+    RemoveCw20Tokens { tokens: Vec<String> },
+    /// Send the balance of `denom` not owed to any grant to `recipient`,
+    /// defaulting to the sender. Only the owner can sweep.
+    SweepSurplus {
+        denom: Denom,
+        recipient: Option<String>,
+    },
+
+    /// Revoke all revocable vesting accounts for the (address, denom) pair funded by the sender,
+    /// or only the given grant when `grant_id` is set. Only the master can revoke.
+    RevokeVestingAccount {
+        address: String,
+        denom: Denom,
+        grant_id: Option<u64>,
     },
 
     ////////////////////////
     /// VestingAccount Operations ///
     ////////////////////////
+    /// Cancel all vesting accounts for the (sender, denom) pair,
+    /// or only the given grant when `grant_id` is set.
+    CancelVestingAccount {
+        denom: Denom,
+        grant_id: Option<u64>,
+        vested_token_recipient: Option<String>,
+    },
+    /// Move all transferable vesting accounts for the (sender, denom) pair, or only the
+    /// given grant when `grant_id` is set, to `new_address`. Grants with the
+    /// `MasterApproval` policy are only marked for transfer until the master approves.
+    TransferVestingAccount {
+        denom: Denom,
+        grant_id: Option<u64>,
+        new_address: String,
+    },
+    /// Split part of grant `grant_id` off into a new grant of `new_beneficiary` with the
+    /// same terms. Called by the beneficiary of a `Transferable` grant or by the master
+    /// of a revocable grant, in which case the vested tokens are paid out first.
+    SplitVestingAccount {
+        denom: Denom,
+        grant_id: u64,
+        amount_or_ratio: SplitAmount,
+        new_beneficiary: String,
+    },
+    /// Claim vested tokens of all grants for the given denoms,
+    /// or only the given grant when `grant_id` is set.
     Claim {
-        denoms: Vec<String>,
+        denoms: Vec<Denom>,
+        grant_id: Option<u64>,
+        recipient: Option<String>,
+    },
+    /// Claim vested tokens of every grant of the sender, without listing denoms.
+    /// Grants are visited in storage order, resuming after the `(denom, grant_id)`
+    /// cursor in `start_after`; the next cursor is emitted as `next_start_after`
+    /// when `limit` grants have been visited.
+    ClaimAll {
         recipient: Option<String>,
+        start_after: Option<(Denom, u64)>,
+        limit: Option<u32>,
+    },
+    /// Allow `operator` to claim the sender's grants of `denom` with `ClaimFor`
+    /// until `expires`.
+    ApproveClaimOperator {
+        operator: String,
+        denom: Denom,
+        expires: Option<Expiration>,
+    },
+    /// Remove the claim approval of `operator` for `denom`.
+    RevokeClaimOperator { operator: String, denom: Denom },
+    /// Set the recipient of the sender's claims when no recipient is given,
+    /// or reset it to the sender.
+    SetClaimRecipient { recipient: Option<String> },
+    /// Opt the sender's grants in to or out of `DistributeVested`. Keepers are paid at
+    /// most `max_bounty`, which defaults to the configured distribution bounty.
+    SetAutoDistribution {
+        enabled: bool,
+        max_bounty: Option<Decimal>,
+    },
+
+    ///////////////////////////
+    /// Operator Operations ///
+    ///////////////////////////
+    /// Claim vested tokens of all grants of `owner` for the given denoms. The tokens
+    /// go to the claim recipient of `owner`. The sender must be approved for every denom.
+    ClaimFor { owner: String, denoms: Vec<Denom> },
+
+    /////////////////////////
+    /// Keeper Operations ///
+    /////////////////////////
+    /// Pay out the vested tokens of opted-in beneficiaries to their claim recipients.
+    /// Anyone can call it and earns the configured bounty. Grants are visited in storage
+    /// order, resuming after the `(address, denom, grant_id)` cursor in `start_after`;
+    /// the next cursor is emitted as `next_start_after` when `limit` grants have been visited.
+    DistributeVested {
+        start_after: Option<(String, Denom, u64)>,
+        limit: Option<u32>,
+    },
+
+    ////////////////////////
+    /// cw721 Operations ///
+    ////////////////////////
+    /// Move grant `token_id` to `recipient`. Only `Transferable` grants can be moved.
+    /// Called by the holder, an approved spender or an operator of the holder.
+    TransferNft { recipient: String, token_id: String },
+    /// Move grant `token_id` to `contract` and notify it with a `Cw721ReceiveMsg`.
+    SendNft {
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    },
+    /// Allow `spender` to transfer grant `token_id` until `expires`.
+    /// Called by the holder or an operator of the holder.
+    Approve {
+        spender: String,
+        token_id: String,
+        expires: Option<Expiration>,
+    },
+    /// Remove the approval of `spender` for grant `token_id`.
+    /// Called by the holder or an operator of the holder.
+    Revoke { spender: String, token_id: String },
+    /// Allow `operator` to transfer, approve spenders of and burn every grant of the sender
+    /// until `expires`.
+    ApproveAll {
+        operator: String,
+        expires: Option<Expiration>,
     },
+    /// Remove the operator approval of `operator` by the sender.
+    RevokeAll { operator: String },
+    /// Cancel grant `token_id`, paying the vested but unclaimed amount to the claim recipient
+    /// of the holder and returning the unvested amount to the master.
+    /// Called by the holder, an approved spender or an operator of the holder.
+    Burn { token_id: String },
+}
+
+/// A single grant of `ExecuteMsg::RegisterVestingAccounts`, funded in the native `denom`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingAccountRegistration {
+    pub master_address: String,
+    pub address: String,
+    pub denom: String,
+    pub vesting_schedule: VestingSchedule,
+    pub revocable: bool,
+    pub transfer_policy: Option<TransferPolicy>,
+}
+
+/// Messages embedded in the `msg` field of a cw20 `Send`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Register a new vesting account with the sent cw20 tokens as deposit.
+    RegisterVestingAccount {
+        master_address: String,
+        address: String,
+        vesting_schedule: VestingSchedule,
+        revocable: bool,
+        transfer_policy: Option<TransferPolicy>,
+    },
+    /// Top up an existing grant with the sent cw20 tokens.
+    TopUpVestingAccount {
+        address: String,
+        grant_id: u64,
+        schedule_extension: ScheduleExtension,
+    },
+}
+
+/// How a top-up deposit is added to the schedule of a grant.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleExtension {
+    /// Scale all amounts of the schedule so that it vests the new total over the same
+    /// time. For periodic vesting the deposit must be divisible by the number of intervals.
+    Scale {},
+    /// Append `periods` intervals of `amount` to a periodic schedule.
+    AppendPeriods { periods: u64 },
+    /// Append unlock points to a custom schedule. Amounts stay cumulative, so the last
+    /// point must unlock the new total.
+    AppendPoints { points: Vec<(u64, Uint128)> },
+}
+
+/// The part of a grant moved to the new grant by `ExecuteMsg::SplitVestingAccount`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitAmount {
+    /// Vesting amount of the new grant
+    Amount(Uint128),
+    /// Share of the vesting amount, rounded down. Periodic schedules are split per interval.
+    Ratio(Decimal),
+}
+
+/// Whether the beneficiary of a grant may move it to another address.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferPolicy {
+    #[default]
+    NonTransferable,
+    Transferable,
+    /// The master has to approve every transfer
+    MasterApproval,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
+    /// Lists the grants of `address`, resuming after the `(denom, grant_id)`
+    /// of the last grant returned.
     VestingAccount {
         address: String,
+        start_after: Option<(Denom, u64)>,
+        limit: Option<u32>,
+        /// Evaluate the grants at this time instead of the block time, nothing is claimable
+        /// at a time before the block time
+        at_time: Option<u64>,
+    },
+    /// Returns the amount still held for the grants of `denom`.
+    VestedTokens { denom: Denom },
+    /// Returns the accounting of all grants of `denom`.
+    DenomStats { denom: Denom },
+    /// Compares the outstanding amount of `denom` with the contract balance.
+    Solvency { denom: Denom },
+    /// Projects the vested and claimable amounts of the grants of `address` in `denom`,
+    /// or only of the given grant, from `from` to `to` every `step` seconds. Nothing is
+    /// claimable at points before the block time.
+    VestingTimeline {
+        address: String,
+        denom: Denom,
+        grant_id: Option<u64>,
+        from: u64,
+        to: u64,
+        step: u64,
+    },
+    /// Runs the registration checks of `schedule` against a deposit of `deposit`
+    /// without registering anything.
+    ValidateVestingSchedule {
+        schedule: VestingSchedule,
+        deposit: Uint128,
+    },
+    /// Lists the accounting of every denom that ever had a grant.
+    AllDenomStats {
         start_after: Option<Denom>,
         limit: Option<u32>,
     },
-    VestedTokens {
-        denom: String,
+    /// Returns the contract `Config`.
+    Config {},
+    /// Lists the allowlisted creators.
+    Creators {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Lists the cw20 token contracts accepted for deposits.
+    Cw20Tokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Lists the grants funded by `master`, resuming after the
+    /// `(address, denom, grant_id)` of the last grant returned.
+    VestingAccountsByMaster {
+        master: String,
+        start_after: Option<(String, Denom, u64)>,
+        limit: Option<u32>,
+        /// Evaluate the grants at this time instead of the block time, nothing is claimable
+        /// at a time before the block time
+        at_time: Option<u64>,
+    },
+    /// Lists all grants, or only those of `denom_filter`, resuming after the
+    /// `(address, denom, grant_id)` of the last grant returned.
+    AllVestingAccounts {
+        start_after: Option<(String, Denom, u64)>,
+        limit: Option<u32>,
+        denom_filter: Option<Denom>,
+        /// Evaluate the grants at this time instead of the block time, nothing is claimable
+        /// at a time before the block time
+        at_time: Option<u64>,
+    },
+
+    /// Lists the claim operators approved by `owner`, resuming after the
+    /// `(operator, denom)` of the last approval returned.
+    ClaimOperators {
+        owner: String,
+        start_after: Option<(String, Denom)>,
+        limit: Option<u32>,
+    },
+    /// Returns where the claims of `owner` are paid out by default.
+    ClaimRecipient { owner: String },
+    /// Returns whether `owner` opted in to automatic distribution.
+    AutoDistribution { owner: String },
+
+    /// cw721: Returns the holder of grant `token_id` and its approved spenders.
+    OwnerOf {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    /// cw721: Returns grant `token_id` as the extension, evaluated at the block time.
+    NftInfo { token_id: String },
+    /// cw721: Combines `OwnerOf` and `NftInfo`.
+    AllNftInfo {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    /// cw721: Returns the approval of `spender` for grant `token_id`.
+    Approval {
+        token_id: String,
+        spender: String,
+        include_expired: Option<bool>,
+    },
+    /// cw721: Returns the approved spenders of grant `token_id`.
+    Approvals {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    /// cw721: Returns the operator approval of `operator` by `owner`.
+    Operator {
+        owner: String,
+        operator: String,
+        include_expired: Option<bool>,
+    },
+    /// cw721: Lists the operators approved by `owner`, in operator order.
+    AllOperators {
+        owner: String,
+        include_expired: Option<bool>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// cw721: Lists the grant ids held by `owner`, in `(denom, grant_id)` order.
+    /// `start_after` must be a grant still held by `owner`.
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// cw721: Lists all grant ids in ascending order.
+    AllTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
     },
+    /// cw721: Returns the number of grants.
+    NumTokens {},
+    /// cw721: Returns the name and symbol of the collection.
+    ContractInfo {},
+    /// cw721: Grants are minted by registration, so there is no minter.
+    Minter {},
 }
 
 #[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
@@ -52,14 +424,216 @@ pub struct VestingAccountResponse {
     pub vestings: Vec<VestingData>,
 }
 
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct VestingAccountsByMasterResponse {
+    pub master: String,
+    pub vestings: Vec<VestingData>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct AllVestingAccountsResponse {
+    pub vestings: Vec<VestingData>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct DenomStatsResponse {
+    pub denom: Denom,
+    pub stats: DenomStats,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct AllDenomStatsResponse {
+    pub stats: Vec<DenomStatsResponse>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct SolvencyResponse {
+    pub denom: Denom,
+    /// Amount owed to active grants
+    pub outstanding: Uint128,
+    /// Amount held by the contract
+    pub balance: Uint128,
+    /// `balance - outstanding`, or zero
+    pub surplus: Uint128,
+    /// `outstanding - balance`, or zero
+    pub deficit: Uint128,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct VestingTimelineResponse {
+    pub address: String,
+    pub denom: Denom,
+    pub points: Vec<TimelinePoint>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct TimelinePoint {
+    pub time: u64,
+    pub vested_amount: Uint128,
+    /// Vested amount not claimed yet, given no further claims
+    pub claimable_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidateVestingScheduleResponse {
+    /// The schedule would be accepted, vesting `vesting_amount` in total.
+    Valid {
+        schedule: VestingSchedule,
+        vesting_amount: Uint128,
+    },
+    /// The schedule would be rejected with `error`.
+    Invalid { error: ScheduleError },
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct CreatorsResponse {
+    pub creators: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct Cw20TokensResponse {
+    pub tokens: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct ClaimOperatorsResponse {
+    pub owner: String,
+    pub operators: Vec<ClaimOperator>,
+}
+
+/// An operator allowed to claim grants of `denom` on behalf of their beneficiary.
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct ClaimOperator {
+    pub operator: String,
+    pub denom: Denom,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct ClaimRecipientResponse {
+    pub owner: String,
+    /// Configured claim recipient, or `owner` if none is set
+    pub recipient: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct AutoDistributionResponse {
+    pub owner: String,
+    pub enabled: bool,
+    /// Highest bounty share accepted by `owner`, zero if not enabled
+    pub max_bounty: Decimal,
+}
+
+/// A spender allowed to transfer a grant on behalf of its holder.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Approval {
+    pub spender: String,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct OwnerOfResponse {
+    /// Beneficiary of the grant
+    pub owner: String,
+    pub approvals: Vec<Approval>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct ApprovalResponse {
+    pub approval: Approval,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct ApprovalsResponse {
+    pub approvals: Vec<Approval>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct OperatorResponse {
+    pub approval: Approval,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct OperatorsResponse {
+    pub operators: Vec<Approval>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct MinterResponse {
+    pub minter: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct NftInfoResponse {
+    /// Always empty, the grant is in `extension`
+    pub token_uri: Option<String>,
+    pub extension: VestingData,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct AllNftInfoResponse {
+    pub access: OwnerOfResponse,
+    pub info: NftInfoResponse,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct TokensResponse {
+    pub tokens: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct NumTokensResponse {
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
+pub struct ContractInfoResponse {
+    pub name: String,
+    pub symbol: String,
+}
+
+/// Sent to the receiving contract of `ExecuteMsg::SendNft`, as in cw721.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw721ReceiveMsg {
+    pub sender: String,
+    pub token_id: String,
+    pub msg: Binary,
+}
+
+impl Cw721ReceiveMsg {
+    /// Wraps the message as `{"receive_nft": ...}` for the receiving contract.
+    pub fn into_cosmos_msg(self, contract_addr: String) -> StdResult<CosmosMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr,
+            msg: to_json_binary(&ReceiverExecuteMsg::ReceiveNft(self))?,
+            funds: vec![],
+        }
+        .into())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum ReceiverExecuteMsg {
+    ReceiveNft(Cw721ReceiveMsg),
+}
+
 #[derive(Serialize, Deserialize, JsonSchema, PartialEq, Debug)]
 pub struct VestingData {
+    pub grant_id: u64,
     pub master_address: String,
-    pub vesting_denom: String,
+    /// Beneficiary of the grant
+    pub address: String,
+    pub vesting_denom: Denom,
     pub vesting_amount: Uint128,
     pub vested_amount: Uint128,
     pub vesting_schedule: VestingSchedule,
     pub claimable_amount: Uint128,
+    pub revocable: bool,
+    pub transfer_policy: TransferPolicy,
+    /// Address the grant moves to once the master approves
+    pub pending_transfer: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -83,6 +657,24 @@ pub enum VestingSchedule {
         vesting_interval: u64, // vesting interval in second unit
         amount: Uint128,       // the amount will be vested in a interval
     },
+    /// LinearVestingWithCliff is used to vest tokens linearly during a time period,
+    /// but nothing can be claimed before cliff_time. When the cliff passes,
+    /// the amount accrued since start_time unlocks at once.
+    LinearVestingWithCliff {
+        start_time: u64,         // vesting start time in second unit
+        end_time: u64,           // vesting end time in second unit
+        cliff_time: u64,         // cliff time in second unit
+        vesting_amount: Uint128, // total vesting amount
+    },
+    /// Custom is used to vest tokens at explicit unlock points.
+    /// Each point is a (time in second unit, cumulative unlocked amount) pair,
+    /// and the cumulative amount of the last point is the total vesting amount.
+    /// With interpolate set, tokens vest linearly between consecutive points.
+    Custom {
+        points: Vec<(u64, Uint128)>,
+        #[serde(default)]
+        interpolate: bool,
+    },
 }
 
 impl VestingSchedule {
@@ -92,23 +684,287 @@ impl VestingSchedule {
                 start_time,
                 end_time,
                 vesting_amount,
+            } => linear_vested_amount(*start_time, *end_time, *vesting_amount, block_time),
+            VestingSchedule::PeriodicVesting {
+                start_time,
+                end_time,
+                vesting_interval,
+                amount,
             } => {
-                let start_time = start_time;
-                let end_time = end_time;
-
                 if &block_time <= start_time {
                     return Ok(Uint128::zero());
                 }
 
+                let num_interval = (end_time - start_time) / vesting_interval;
                 if &block_time >= end_time {
-                    return Ok(*vesting_amount);
+                    return Ok(amount.checked_mul(Uint128::from(num_interval))?);
+                }
+
+                let passed_interval = (block_time - start_time) / vesting_interval;
+                Ok(amount.checked_mul(Uint128::from(passed_interval))?)
+            }
+            VestingSchedule::LinearVestingWithCliff {
+                start_time,
+                end_time,
+                cliff_time,
+                vesting_amount,
+            } => {
+                if &block_time < cliff_time {
+                    return Ok(Uint128::zero());
+                }
+
+                linear_vested_amount(*start_time, *end_time, *vesting_amount, block_time)
+            }
+            VestingSchedule::Custom {
+                points,
+                interpolate,
+            } => {
+                let passed = points
+                    .iter()
+                    .take_while(|(time, _)| *time <= block_time)
+                    .count();
+                if passed == 0 {
+                    return Ok(Uint128::zero());
+                }
+
+                let (prev_time, prev_amount) = points[passed - 1];
+                match points.get(passed) {
+                    Some((next_time, next_amount)) if *interpolate => {
+                        let interpolated = next_amount
+                            .checked_sub(prev_amount)?
+                            .checked_mul(Uint128::from(block_time - prev_time))?
+                            .checked_div(Uint128::from(next_time - prev_time))?;
+                        Ok(prev_amount.checked_add(interpolated)?)
+                    }
+                    _ => Ok(prev_amount),
+                }
+            }
+        }
+    }
+
+    /// Total amount vested once the schedule has ended.
+    pub fn total_amount(&self) -> Result<Uint128, ScheduleError> {
+        match self {
+            VestingSchedule::LinearVesting { vesting_amount, .. }
+            | VestingSchedule::LinearVestingWithCliff { vesting_amount, .. } => Ok(*vesting_amount),
+            VestingSchedule::PeriodicVesting {
+                start_time,
+                end_time,
+                vesting_interval,
+                amount,
+            } => {
+                if *vesting_interval == 0 {
+                    return Err(ScheduleError::ZeroVestingInterval {});
+                }
+
+                let num_interval = end_time.saturating_sub(*start_time) / vesting_interval;
+                amount
+                    .checked_mul(Uint128::from(num_interval))
+                    .map_err(|_| ScheduleError::VestingAmountOverflow {})
+            }
+            VestingSchedule::Custom { points, .. } => points
+                .last()
+                .map(|(_, amount)| *amount)
+                .ok_or(ScheduleError::NoUnlockPoints {}),
+        }
+    }
+
+    /// Returns the schedule with `amount` added according to `extension`.
+    pub fn extend(
+        &self,
+        extension: &ScheduleExtension,
+        amount: Uint128,
+    ) -> Result<VestingSchedule, ScheduleError> {
+        let total_amount = self.total_amount()?;
+        let new_total = total_amount
+            .checked_add(amount)
+            .map_err(|_| ScheduleError::VestingAmountOverflow {})?;
+
+        let mut schedule = self.clone();
+        match (&mut schedule, extension) {
+            (
+                VestingSchedule::LinearVesting { vesting_amount, .. }
+                | VestingSchedule::LinearVestingWithCliff { vesting_amount, .. },
+                ScheduleExtension::Scale {},
+            ) => *vesting_amount = new_total,
+            (
+                VestingSchedule::PeriodicVesting {
+                    start_time,
+                    end_time,
+                    vesting_interval,
+                    amount: interval_amount,
+                },
+                ScheduleExtension::Scale {},
+            ) => {
+                let intervals = (*end_time - *start_time) / *vesting_interval;
+                if !(amount % Uint128::from(intervals)).is_zero() {
+                    return Err(ScheduleError::IndivisibleTopUp { amount, intervals });
+                }
+                *interval_amount += amount / Uint128::from(intervals);
+            }
+            (
+                VestingSchedule::PeriodicVesting {
+                    end_time,
+                    vesting_interval,
+                    amount: interval_amount,
+                    ..
+                },
+                ScheduleExtension::AppendPeriods { periods },
+            ) => {
+                let expected = interval_amount
+                    .checked_mul(Uint128::from(*periods))
+                    .map_err(|_| ScheduleError::VestingAmountOverflow {})?;
+                if expected != amount {
+                    return Err(ScheduleError::DepositMismatch {
+                        deposit_amount: amount,
+                        vesting_amount: expected,
+                    });
+                }
+                *end_time = periods
+                    .checked_mul(*vesting_interval)
+                    .and_then(|extension| end_time.checked_add(extension))
+                    .ok_or(ScheduleError::VestingAmountOverflow {})?;
+            }
+            (VestingSchedule::Custom { points, .. }, ScheduleExtension::Scale {}) => {
+                // rounding down keeps the points non-decreasing, the last one is exact
+                for point in points.iter_mut() {
+                    point.1 = point
+                        .1
+                        .checked_multiply_ratio(new_total, total_amount)
+                        .map_err(|_| ScheduleError::VestingAmountOverflow {})?;
+                }
+            }
+            (
+                VestingSchedule::Custom { points, .. },
+                ScheduleExtension::AppendPoints { points: new_points },
+            ) => {
+                points.extend(new_points.iter().cloned());
+            }
+            _ => return Err(ScheduleError::UnsupportedExtension {}),
+        }
+
+        // the extended schedule may have started already, so only check its shape
+        schedule.validate(0, new_total)?;
+        Ok(schedule)
+    }
+
+    /// Splits the schedule in two with the same timing, returning the remaining and the
+    /// split off schedule. Unlock points are scaled down, so the two never unlock more
+    /// than the original.
+    pub fn split(
+        &self,
+        split: &SplitAmount,
+    ) -> Result<(VestingSchedule, VestingSchedule), ScheduleError> {
+        let total_amount = self.total_amount()?;
+        let amount = match (self, split) {
+            (_, SplitAmount::Amount(amount)) => *amount,
+            (
+                VestingSchedule::PeriodicVesting {
+                    start_time,
+                    end_time,
+                    vesting_interval,
+                    amount,
+                },
+                SplitAmount::Ratio(ratio),
+            ) => {
+                // split per interval to keep the amount divisible
+                let intervals = (end_time - start_time) / vesting_interval;
+                let interval_amount = amount
+                    .checked_mul_floor(*ratio)
+                    .map_err(|_| ScheduleError::VestingAmountOverflow {})?;
+                interval_amount
+                    .checked_mul(Uint128::from(intervals))
+                    .map_err(|_| ScheduleError::VestingAmountOverflow {})?
+            }
+            (_, SplitAmount::Ratio(ratio)) => total_amount
+                .checked_mul_floor(*ratio)
+                .map_err(|_| ScheduleError::VestingAmountOverflow {})?,
+        };
+        if amount.is_zero() || amount >= total_amount {
+            return Err(ScheduleError::InvalidSplit {
+                amount,
+                vesting_amount: total_amount,
+            });
+        }
+
+        let scale = |new_total: Uint128| -> Result<VestingSchedule, ScheduleError> {
+            let mut schedule = self.clone();
+            match &mut schedule {
+                VestingSchedule::LinearVesting { vesting_amount, .. }
+                | VestingSchedule::LinearVestingWithCliff { vesting_amount, .. } => {
+                    *vesting_amount = new_total
+                }
+                VestingSchedule::PeriodicVesting {
+                    start_time,
+                    end_time,
+                    vesting_interval,
+                    amount: interval_amount,
+                } => {
+                    let intervals = (*end_time - *start_time) / *vesting_interval;
+                    if !(new_total % Uint128::from(intervals)).is_zero() {
+                        return Err(ScheduleError::IndivisibleSplit {
+                            amount: new_total,
+                            intervals,
+                        });
+                    }
+                    *interval_amount = new_total / Uint128::from(intervals);
                 }
+                VestingSchedule::Custom { points, .. } => {
+                    for point in points.iter_mut() {
+                        point.1 = point
+                            .1
+                            .checked_multiply_ratio(new_total, total_amount)
+                            .map_err(|_| ScheduleError::VestingAmountOverflow {})?;
+                    }
+                }
+            }
+            schedule.validate(0, new_total)?;
+            Ok(schedule)
+        };
+        let split_off = scale(amount)?;
+        Ok((scale(total_amount - amount)?, split_off))
+    }
 
-                let vested_token = vesting_amount
-                    .checked_mul(Uint128::from(block_time - start_time))?
-                    .checked_div(Uint128::from(end_time - start_time))?;
+    /// Runs the checks applied when registering a grant with this schedule and a
+    /// deposit of `deposit_amount` at `block_time`. Returns the total vesting amount.
+    pub fn validate(
+        &self,
+        block_time: u64,
+        deposit_amount: Uint128,
+    ) -> Result<Uint128, ScheduleError> {
+        // every schedule must vest its whole deposit, so nothing is vested without one
+        if deposit_amount.is_zero() {
+            return Err(ScheduleError::ZeroVestingAmount {});
+        }
 
-                Ok(vested_token)
+        match *self {
+            VestingSchedule::LinearVesting {
+                start_time,
+                end_time,
+                vesting_amount,
+            } => {
+                if vesting_amount != deposit_amount {
+                    return Err(ScheduleError::DepositMismatch {
+                        deposit_amount,
+                        vesting_amount,
+                    });
+                }
+
+                if start_time < block_time {
+                    return Err(ScheduleError::StartTimeInPast {
+                        start_time,
+                        block_time,
+                    });
+                }
+
+                if end_time <= start_time {
+                    return Err(ScheduleError::InvalidEndTime {
+                        start_time,
+                        end_time,
+                    });
+                }
+
+                Ok(vesting_amount)
             }
             VestingSchedule::PeriodicVesting {
                 start_time,
@@ -116,25 +972,144 @@ impl VestingSchedule {
                 vesting_interval,
                 amount,
             } => {
-                let start_time = start_time;
-                let end_time = end_time;
+                if amount.is_zero() {
+                    return Err(ScheduleError::ZeroVestingAmount {});
+                }
 
-                if &block_time <= start_time {
-                    return Ok(Uint128::zero());
+                if start_time < block_time {
+                    return Err(ScheduleError::StartTimeInPast {
+                        start_time,
+                        block_time,
+                    });
                 }
 
-                let num_interval = (end_time - start_time) / vesting_interval;
-                if &block_time >= end_time {
-                    return Ok(amount.checked_mul(Uint128::from(num_interval))?);
+                if end_time <= start_time {
+                    return Err(ScheduleError::InvalidEndTime {
+                        start_time,
+                        end_time,
+                    });
                 }
 
-                let passed_interval = (block_time - start_time) / vesting_interval;
-                Ok(amount.checked_mul(Uint128::from(passed_interval))?)
+                if vesting_interval == 0 {
+                    return Err(ScheduleError::ZeroVestingInterval {});
+                }
+
+                let time_period = end_time - start_time;
+                if time_period % vesting_interval != 0 {
+                    return Err(ScheduleError::IndivisibleVestingPeriod {
+                        period: time_period,
+                        vesting_interval,
+                    });
+                }
+
+                let num_interval = time_period / vesting_interval;
+                let vesting_amount = amount
+                    .checked_mul(Uint128::from(num_interval))
+                    .map_err(|_| ScheduleError::VestingAmountOverflow {})?;
+                if vesting_amount != deposit_amount {
+                    return Err(ScheduleError::DepositMismatch {
+                        deposit_amount,
+                        vesting_amount,
+                    });
+                }
+
+                Ok(vesting_amount)
+            }
+            VestingSchedule::LinearVestingWithCliff {
+                start_time,
+                end_time,
+                cliff_time,
+                vesting_amount,
+            } => {
+                if vesting_amount != deposit_amount {
+                    return Err(ScheduleError::DepositMismatch {
+                        deposit_amount,
+                        vesting_amount,
+                    });
+                }
+
+                if start_time < block_time {
+                    return Err(ScheduleError::StartTimeInPast {
+                        start_time,
+                        block_time,
+                    });
+                }
+
+                if end_time <= start_time {
+                    return Err(ScheduleError::InvalidEndTime {
+                        start_time,
+                        end_time,
+                    });
+                }
+
+                if cliff_time < start_time || cliff_time > end_time {
+                    return Err(ScheduleError::InvalidCliffTime {
+                        start_time,
+                        end_time,
+                        cliff_time,
+                    });
+                }
+
+                Ok(vesting_amount)
+            }
+            VestingSchedule::Custom { ref points, .. } => {
+                let (start_time, _) = match points.first() {
+                    Some(point) => *point,
+                    None => return Err(ScheduleError::NoUnlockPoints {}),
+                };
+
+                if start_time < block_time {
+                    return Err(ScheduleError::StartTimeInPast {
+                        start_time,
+                        block_time,
+                    });
+                }
+
+                for (index, window) in points.windows(2).enumerate() {
+                    if window[1].0 <= window[0].0 {
+                        return Err(ScheduleError::UnorderedUnlockPoints { index: index + 1 });
+                    }
+
+                    if window[1].1 < window[0].1 {
+                        return Err(ScheduleError::DecreasingUnlockPoints { index: index + 1 });
+                    }
+                }
+
+                let (_, vesting_amount) = points[points.len() - 1];
+                if vesting_amount != deposit_amount {
+                    return Err(ScheduleError::DepositMismatch {
+                        deposit_amount,
+                        vesting_amount,
+                    });
+                }
+
+                Ok(vesting_amount)
             }
         }
     }
 }
 
+fn linear_vested_amount(
+    start_time: u64,
+    end_time: u64,
+    vesting_amount: Uint128,
+    block_time: u64,
+) -> StdResult<Uint128> {
+    if block_time <= start_time {
+        return Ok(Uint128::zero());
+    }
+
+    if block_time >= end_time {
+        return Ok(vesting_amount);
+    }
+
+    let vested_token = vesting_amount
+        .checked_mul(Uint128::from(block_time - start_time))?
+        .checked_div(Uint128::from(end_time - start_time))?;
+
+    Ok(vested_token)
+}
+
 #[test]
 fn periodic_vesting_vested_amount_hack() {
     let schedule = VestingSchedule::PeriodicVesting {
@@ -199,11 +1174,316 @@ fn periodic_vesting_vested_amount() {
     );
 }
 
+#[test]
+fn linear_vesting_with_cliff_vested_amount() {
+    let schedule = VestingSchedule::LinearVestingWithCliff {
+        start_time: 100,
+        end_time: 110,
+        cliff_time: 104,
+        vesting_amount: Uint128::new(1000000u128),
+    };
+
+    assert_eq!(schedule.vested_amount(100).unwrap(), Uint128::zero());
+    assert_eq!(schedule.vested_amount(103).unwrap(), Uint128::zero());
+    // accrual since start_time unlocks at the cliff
+    assert_eq!(
+        schedule.vested_amount(104).unwrap(),
+        Uint128::new(400000u128)
+    );
+    assert_eq!(
+        schedule.vested_amount(105).unwrap(),
+        Uint128::new(500000u128)
+    );
+    assert_eq!(
+        schedule.vested_amount(110).unwrap(),
+        Uint128::new(1000000u128)
+    );
+    assert_eq!(
+        schedule.vested_amount(115).unwrap(),
+        Uint128::new(1000000u128)
+    );
+}
+
+#[test]
+fn custom_vesting_vested_amount() {
+    let points = vec![
+        (100, Uint128::new(100000u128)),
+        (200, Uint128::new(400000u128)),
+        (300, Uint128::new(1000000u128)),
+    ];
+
+    let schedule = VestingSchedule::Custom {
+        points: points.clone(),
+        interpolate: false,
+    };
+    assert_eq!(schedule.vested_amount(99).unwrap(), Uint128::zero());
+    assert_eq!(
+        schedule.vested_amount(100).unwrap(),
+        Uint128::new(100000u128)
+    );
+    assert_eq!(
+        schedule.vested_amount(150).unwrap(),
+        Uint128::new(100000u128)
+    );
+    assert_eq!(
+        schedule.vested_amount(200).unwrap(),
+        Uint128::new(400000u128)
+    );
+    assert_eq!(
+        schedule.vested_amount(400).unwrap(),
+        Uint128::new(1000000u128)
+    );
+
+    let schedule = VestingSchedule::Custom {
+        points,
+        interpolate: true,
+    };
+    assert_eq!(schedule.vested_amount(99).unwrap(), Uint128::zero());
+    assert_eq!(
+        schedule.vested_amount(100).unwrap(),
+        Uint128::new(100000u128)
+    );
+    assert_eq!(
+        schedule.vested_amount(150).unwrap(),
+        Uint128::new(250000u128)
+    );
+    assert_eq!(
+        schedule.vested_amount(250).unwrap(),
+        Uint128::new(700000u128)
+    );
+    assert_eq!(
+        schedule.vested_amount(400).unwrap(),
+        Uint128::new(1000000u128)
+    );
+}
+
+#[test]
+fn schedule_extension() {
+    let linear = VestingSchedule::LinearVesting {
+        start_time: 100,
+        end_time: 110,
+        vesting_amount: Uint128::new(1000),
+    };
+    assert_eq!(
+        linear
+            .extend(&ScheduleExtension::Scale {}, Uint128::new(500))
+            .unwrap(),
+        VestingSchedule::LinearVesting {
+            start_time: 100,
+            end_time: 110,
+            vesting_amount: Uint128::new(1500),
+        }
+    );
+    assert_eq!(
+        linear.extend(
+            &ScheduleExtension::AppendPeriods { periods: 1 },
+            Uint128::new(500)
+        ),
+        Err(ScheduleError::UnsupportedExtension {})
+    );
+
+    let periodic = VestingSchedule::PeriodicVesting {
+        start_time: 100,
+        end_time: 140,
+        vesting_interval: 10,
+        amount: Uint128::new(100),
+    };
+    assert_eq!(
+        periodic
+            .extend(&ScheduleExtension::Scale {}, Uint128::new(200))
+            .unwrap(),
+        VestingSchedule::PeriodicVesting {
+            start_time: 100,
+            end_time: 140,
+            vesting_interval: 10,
+            amount: Uint128::new(150),
+        }
+    );
+    assert_eq!(
+        periodic.extend(&ScheduleExtension::Scale {}, Uint128::new(201)),
+        Err(ScheduleError::IndivisibleTopUp {
+            amount: Uint128::new(201),
+            intervals: 4,
+        })
+    );
+    assert_eq!(
+        periodic
+            .extend(
+                &ScheduleExtension::AppendPeriods { periods: 2 },
+                Uint128::new(200)
+            )
+            .unwrap(),
+        VestingSchedule::PeriodicVesting {
+            start_time: 100,
+            end_time: 160,
+            vesting_interval: 10,
+            amount: Uint128::new(100),
+        }
+    );
+    assert_eq!(
+        periodic.extend(
+            &ScheduleExtension::AppendPeriods { periods: 2 },
+            Uint128::new(150)
+        ),
+        Err(ScheduleError::DepositMismatch {
+            deposit_amount: Uint128::new(150),
+            vesting_amount: Uint128::new(200),
+        })
+    );
+
+    let custom = VestingSchedule::Custom {
+        points: vec![(100, Uint128::new(200)), (200, Uint128::new(500))],
+        interpolate: true,
+    };
+    assert_eq!(
+        custom
+            .extend(&ScheduleExtension::Scale {}, Uint128::new(250))
+            .unwrap(),
+        VestingSchedule::Custom {
+            points: vec![(100, Uint128::new(300)), (200, Uint128::new(750))],
+            interpolate: true,
+        }
+    );
+    assert_eq!(
+        custom
+            .extend(
+                &ScheduleExtension::AppendPoints {
+                    points: vec![(300, Uint128::new(800))]
+                },
+                Uint128::new(300)
+            )
+            .unwrap(),
+        VestingSchedule::Custom {
+            points: vec![
+                (100, Uint128::new(200)),
+                (200, Uint128::new(500)),
+                (300, Uint128::new(800))
+            ],
+            interpolate: true,
+        }
+    );
+    assert_eq!(
+        custom.extend(
+            &ScheduleExtension::AppendPoints {
+                points: vec![(150, Uint128::new(800))]
+            },
+            Uint128::new(300)
+        ),
+        Err(ScheduleError::UnorderedUnlockPoints { index: 2 })
+    );
+    assert_eq!(
+        custom.extend(
+            &ScheduleExtension::AppendPoints {
+                points: vec![(300, Uint128::new(700))]
+            },
+            Uint128::new(300)
+        ),
+        Err(ScheduleError::DepositMismatch {
+            deposit_amount: Uint128::new(800),
+            vesting_amount: Uint128::new(700),
+        })
+    );
+}
+
+#[test]
+fn schedule_split() {
+    let linear = VestingSchedule::LinearVesting {
+        start_time: 100,
+        end_time: 110,
+        vesting_amount: Uint128::new(1000),
+    };
+    let linear_with = |vesting_amount: u128| VestingSchedule::LinearVesting {
+        start_time: 100,
+        end_time: 110,
+        vesting_amount: Uint128::new(vesting_amount),
+    };
+    assert_eq!(
+        linear.split(&SplitAmount::Amount(Uint128::new(300))),
+        Ok((linear_with(700), linear_with(300)))
+    );
+    assert_eq!(
+        linear.split(&SplitAmount::Ratio(Decimal::percent(25))),
+        Ok((linear_with(750), linear_with(250)))
+    );
+    assert_eq!(
+        linear.split(&SplitAmount::Amount(Uint128::new(1000))),
+        Err(ScheduleError::InvalidSplit {
+            amount: Uint128::new(1000),
+            vesting_amount: Uint128::new(1000),
+        })
+    );
+    assert_eq!(
+        linear.split(&SplitAmount::Ratio(Decimal::zero())),
+        Err(ScheduleError::InvalidSplit {
+            amount: Uint128::zero(),
+            vesting_amount: Uint128::new(1000),
+        })
+    );
+
+    let periodic_with = |amount: u128| VestingSchedule::PeriodicVesting {
+        start_time: 100,
+        end_time: 140,
+        vesting_interval: 10,
+        amount: Uint128::new(amount),
+    };
+    let periodic = periodic_with(25);
+    assert_eq!(
+        periodic.split(&SplitAmount::Amount(Uint128::new(40))),
+        Ok((periodic_with(15), periodic_with(10)))
+    );
+    assert_eq!(
+        periodic.split(&SplitAmount::Amount(Uint128::new(30))),
+        Err(ScheduleError::IndivisibleSplit {
+            amount: Uint128::new(30),
+            intervals: 4,
+        })
+    );
+    // 25 * 0.3 rounds down to 7 per interval
+    assert_eq!(
+        periodic.split(&SplitAmount::Ratio(Decimal::percent(30))),
+        Ok((periodic_with(18), periodic_with(7)))
+    );
+
+    let custom = VestingSchedule::Custom {
+        points: vec![(100, Uint128::new(5)), (200, Uint128::new(10))],
+        interpolate: false,
+    };
+    let (remaining, split_off) = custom
+        .split(&SplitAmount::Ratio(Decimal::percent(50)))
+        .unwrap();
+    assert_eq!(
+        remaining,
+        VestingSchedule::Custom {
+            points: vec![(100, Uint128::new(2)), (200, Uint128::new(5))],
+            interpolate: false,
+        }
+    );
+    assert_eq!(
+        split_off,
+        VestingSchedule::Custom {
+            points: vec![(100, Uint128::new(2)), (200, Uint128::new(5))],
+            interpolate: false,
+        }
+    );
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum SudoMsg {
-    UpdateVestingContract { address: Addr },
+    UpdateVestingContract {
+        address: Addr,
+    },
+    UpdateConfig {
+        owner: Option<Addr>,
+        app_id: Option<u64>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct MigrateMsg {}
+pub struct MigrateMsg {
+    /// Owner of the configuration, required when migrating from a version without one
+    pub owner: Option<String>,
+    /// App id of the configuration written when migrating from a version without one,
+    /// defaults to the stored app id
+    pub app_id: Option<u64>,
+}
@@ -1,21 +1,113 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::msg::VestingSchedule;
-use cosmwasm_std::Uint128;
-use cw20::Denom;
-use cw_storage_plus::{Item, Map};
+use crate::msg::{Approval, TransferPolicy, VestingSchedule};
+use cosmwasm_std::{Addr, Decimal, Empty, StdError, StdResult, Storage, Uint128};
+use cw20::{Denom, Expiration};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex, UniqueIndex};
 
-/// Maps address of user and vested token denom to the vesting details.
-pub const VESTING_ACCOUNTS: Map<(&str, &str), VestingAccount> = Map::new("vesting_accounts");
-/// Maps denom to the total amount vested
-pub const VESTED_BY_DENOM: Map<&str, Uint128> = Map::new("vested_by_denom");
+/// Primary key of a grant: address of user, vested token denom key and grant id.
+pub type VestingAccountKey<'a> = (&'a str, &'a str, u64);
 
-pub const APP_ID: Item<u64> = Item::new("app_id");
+pub struct VestingAccountIndexes<'a> {
+    /// Grants by the master address that funded them
+    pub master: MultiIndex<'a, String, VestingAccount, VestingAccountKey<'a>>,
+    /// Grants by the key of their vesting denom
+    pub denom: MultiIndex<'a, String, VestingAccount, VestingAccountKey<'a>>,
+    /// Grants by grant id, which doubles as cw721 token id
+    pub grant: UniqueIndex<'a, u64, VestingAccount, VestingAccountKey<'a>>,
+}
+
+impl<'a> IndexList<VestingAccount> for VestingAccountIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<VestingAccount>> + '_> {
+        let v: Vec<&dyn Index<VestingAccount>> = vec![&self.master, &self.denom, &self.grant];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Maps address of user, vested token denom key and grant id to the vesting details.
+pub fn vesting_accounts<'a>(
+) -> IndexedMap<'a, VestingAccountKey<'a>, VestingAccount, VestingAccountIndexes<'a>> {
+    let indexes = VestingAccountIndexes {
+        master: MultiIndex::new(
+            |account| account.master_address.clone(),
+            "vesting_accounts",
+            "vesting_accounts__master",
+        ),
+        denom: MultiIndex::new(
+            |account| denom_to_key(&account.vesting_denom),
+            "vesting_accounts",
+            "vesting_accounts__denom",
+        ),
+        grant: UniqueIndex::new(|account| account.grant_id, "vesting_accounts__grant"),
+    };
+    IndexedMap::new("vesting_accounts", indexes)
+}
+/// Last grant id handed out by `next_grant_id`
+pub const GRANT_COUNT: Item<u64> = Item::new("grant_count");
+/// Maps denom key to the accounting of all grants in that denom
+pub const DENOM_STATS: Map<&str, DenomStats> = Map::new("denom_stats");
+
+pub const CONFIG: Item<Config> = Item::new("config");
+/// Addresses allowed to register vesting accounts unless registration is open
+pub const CREATORS: Map<&Addr, Empty> = Map::new("creators");
+/// cw20 token contracts whose deposits are accepted
+pub const CW20_TOKENS: Map<&Addr, Empty> = Map::new("cw20_tokens");
+/// Maps beneficiary, operator and denom key to the expiration of the claim approval
+pub const CLAIM_OPERATORS: Map<(&str, &str, &str), Expiration> = Map::new("claim_operators");
+/// Maps holder and operator to the expiration of the cw721 approval to move all grants of the holder
+pub const NFT_OPERATORS: Map<(&str, &str), Expiration> = Map::new("nft_operators");
+/// Maps beneficiary to the default recipient of its claims
+pub const CLAIM_RECIPIENTS: Map<&str, String> = Map::new("claim_recipients");
+/// Maps beneficiaries whose vested tokens are paid out by `DistributeVested`
+/// to the highest bounty share they accepted
+pub const AUTO_DISTRIBUTION: Map<&str, Decimal> = Map::new("auto_distribution");
+
+/// App id of the layout before `CONFIG`, read once by `migrate`
+pub const LEGACY_APP_ID: Item<u64> = Item::new("app_id");
+/// Grants of the layout before `CONFIG`, keyed by address and native denom, re-keyed by `migrate`
+pub const LEGACY_VESTING_ACCOUNTS: Map<(&str, &str), LegacyVestingAccount> =
+    Map::new("vesting_accounts");
+/// Amount vested by native denom in the layout before `DENOM_STATS`, dropped by `migrate`
+pub const LEGACY_VESTED_BY_DENOM: Map<&str, Uint128> = Map::new("vested_by_denom");
+
+/// This struct holds the contract configuration.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Config {
+    /// Owner allowed to update the configuration
+    pub owner: Addr,
+    pub app_id: u64,
+    /// Vesting contract linked to the app, set by chain governance
+    pub vesting_contract: Option<Addr>,
+    /// Whether anyone may register vesting accounts, or only `CREATORS`
+    pub open_registration: bool,
+    /// Share of each automatic distribution paid to the caller of `DistributeVested`
+    pub distribution_bounty: Decimal,
+}
 
 /// This struct holds necessary vesting details.
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct VestingAccount {
+    pub grant_id: u64,
+    pub master_address: String,
+    pub address: String,
+    pub vesting_denom: Denom,
+    pub vesting_amount: Uint128,
+    pub vesting_schedule: VestingSchedule,
+    pub claimed_amount: Uint128,
+    /// Whether the master is allowed to revoke the grant
+    pub revocable: bool,
+    /// Whether the beneficiary may move the grant to another address
+    pub transfer_policy: TransferPolicy,
+    /// New beneficiary requested under `TransferPolicy::MasterApproval`
+    pub pending_transfer: Option<String>,
+    /// Spenders allowed to transfer the grant as a cw721 token
+    pub approvals: Vec<Approval>,
+}
+
+/// Vesting details as stored before grants had ids, only read by `migrate`.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct LegacyVestingAccount {
     pub master_address: String,
     pub address: String,
     pub vesting_denom: String,
@@ -24,9 +116,43 @@ pub struct VestingAccount {
     pub claimed_amount: Uint128,
 }
 
-pub fn denom_to_key(denom: Denom) -> String {
+/// Accounting of all grants of a single denom.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, JsonSchema, Debug)]
+pub struct DenomStats {
+    /// Total amount deposited into grants
+    pub deposited: Uint128,
+    /// Total amount paid out to beneficiaries
+    pub claimed: Uint128,
+    /// Total amount returned to masters on revocation or cancellation
+    pub returned: Uint128,
+    /// Number of grants which are neither fully claimed nor deregistered
+    pub active_grants: u64,
+    /// Amount still held for active grants, `deposited - claimed - returned`
+    pub outstanding: Uint128,
+}
+
+/// Storage key of a denom, keeping native and cw20 denoms apart.
+pub fn denom_to_key(denom: &Denom) -> String {
     match denom {
         Denom::Cw20(addr) => format!("cw20-{}", addr),
         Denom::Native(denom) => format!("native-{}", denom),
     }
 }
+
+/// Inverse of `denom_to_key`.
+pub fn key_to_denom(key: &str) -> StdResult<Denom> {
+    if let Some(addr) = key.strip_prefix("cw20-") {
+        Ok(Denom::Cw20(Addr::unchecked(addr)))
+    } else if let Some(denom) = key.strip_prefix("native-") {
+        Ok(Denom::Native(denom.to_string()))
+    } else {
+        Err(StdError::generic_err(format!("invalid denom key {}", key)))
+    }
+}
+
+/// Returns a new globally unique grant id, starting from 1.
+pub fn next_grant_id(storage: &mut dyn Storage) -> StdResult<u64> {
+    let grant_id = GRANT_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    GRANT_COUNT.save(storage, &grant_id)?;
+    Ok(grant_id)
+}